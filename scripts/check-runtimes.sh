@@ -0,0 +1,10 @@
+#!/bin/sh
+# Lint and test wezpy with each executor selectable through cargo features
+set -eu
+cd "$(dirname "$0")/.."
+
+for features in "--features async-std-runtime" "--features tokio-runtime"; do
+    echo "== $features"
+    cargo clippy --all-targets --no-default-features $features -- -D warnings
+    cargo test --no-default-features $features
+done
@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use pyo3::prelude::*;
+
+/// A client attached to the mux server, eg. a gui or a `wezterm cli` invocation
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct ClientInfo {
+    hostname: String,
+    username: String,
+    pid: u32,
+    /// Start time of the client process, seconds since the unix epoch
+    epoch: u64,
+    /// Distinguishes the connections made by the same process
+    id: usize,
+    connected_at: DateTime<Utc>,
+    last_input: DateTime<Utc>,
+    active_workspace: Option<String>,
+    focused_pane_id: Option<usize>,
+}
+
+#[pymethods]
+impl ClientInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "ClientInfo(hostname={:?}, username={:?}, pid={}, active_workspace={:?}, focused_pane_id={:?})",
+            self.hostname, self.username, self.pid, self.active_workspace, self.focused_pane_id
+        )
+    }
+}
+
+impl From<mux::client::ClientInfo> for ClientInfo {
+    fn from(info: mux::client::ClientInfo) -> Self {
+        Self {
+            hostname: info.client_id.hostname.clone(),
+            username: info.client_id.username.clone(),
+            pid: info.client_id.pid,
+            epoch: info.client_id.epoch,
+            id: info.client_id.id,
+            connected_at: info.connected_at,
+            last_input: info.last_input,
+            active_workspace: info.active_workspace,
+            focused_pane_id: info.focused_pane_id,
+        }
+    }
+}
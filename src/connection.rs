@@ -0,0 +1,630 @@
+use crate::errors::{ConnectFailed, Disconnected, ErrorReply};
+use crate::runtime;
+use anyhow::{Context, Result};
+use codec::{GetCodecVersionResponse, Pdu, SetClientId};
+use config::{SshDomain, TlsDomainClient, UnixDomain};
+use mux::client::ClientId;
+use pyo3::prelude::*;
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+    time::Duration,
+};
+use wezterm_client::client::Client;
+
+/// The user's wezterm config, loaded on first use the way `wezterm cli` loads it at
+/// startup, so that its unix domains and spawn defaults apply
+pub(crate) fn configuration() -> config::ConfigHandle {
+    static LOAD: Once = Once::new();
+    LOAD.call_once(config::reload);
+    config::configuration()
+}
+
+/// Describes how to reach the wezterm instance a `WeztermClient` talks to
+#[derive(Clone, Debug)]
+pub(crate) enum ConnectionConfig {
+    Unix(UnixOptions),
+    Tls(Box<TlsDomainClient>),
+    Ssh(Box<SshDomain>),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct UnixOptions {
+    /// Explicit socket to connect to, takes precedence over `domain`
+    pub socket_path: Option<PathBuf>,
+    /// Name of a unix domain from the wezterm config file
+    pub domain: Option<String>,
+    /// Gui class name used to locate the gui socket, see `wezterm start --class`
+    pub class_name: String,
+    pub prefer_mux: bool,
+    pub no_auto_start: bool,
+}
+
+impl ConnectionConfig {
+    pub fn unix(
+        socket_path: Option<PathBuf>,
+        class_name: Option<String>,
+        prefer_mux: bool,
+        no_auto_start: bool,
+        domain: Option<String>,
+    ) -> Self {
+        ConnectionConfig::Unix(UnixOptions {
+            socket_path,
+            domain,
+            class_name: class_name
+                .unwrap_or_else(|| wezterm_gui_subcommands::DEFAULT_WINDOW_CLASS.to_string()),
+            prefer_mux,
+            no_auto_start,
+        })
+    }
+
+    /// Build the config for a remote mux server reached over TLS, checking that
+    /// the certificate files exist before any connection is attempted
+    pub fn tls(options: TlsOptions) -> Result<Self> {
+        let TlsOptions {
+            remote_address,
+            pem_cert,
+            pem_ca,
+            pem_private_key,
+            pem_root_certs,
+            bootstrap_via_ssh,
+            expected_cn,
+            accept_invalid_hostnames,
+        } = options;
+
+        match remote_address.rsplit_once(':') {
+            Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {}
+            _ => anyhow::bail!(
+                "Expected remote_address of the form 'host:port', got {remote_address:?}"
+            ),
+        }
+        if pem_cert.is_some() != pem_private_key.is_some() {
+            anyhow::bail!("pem_cert and pem_private_key must be given together");
+        }
+        for path in [&pem_cert, &pem_ca, &pem_private_key].into_iter().flatten() {
+            check_file(path)?;
+        }
+        for path in &pem_root_certs {
+            if !path.exists() {
+                anyhow::bail!("Root certificate path {} does not exist", path.display());
+            }
+        }
+
+        Ok(ConnectionConfig::Tls(Box::new(TlsDomainClient {
+            name: remote_address.clone(),
+            bootstrap_via_ssh,
+            remote_address,
+            pem_private_key,
+            pem_cert,
+            pem_ca,
+            pem_root_certs,
+            accept_invalid_hostnames,
+            expected_cn,
+            read_timeout: config::default_read_timeout(),
+            write_timeout: config::default_write_timeout(),
+            local_echo_threshold_ms: config::default_local_echo_threshold_ms(),
+            ..Default::default()
+        })))
+    }
+
+    /// Build the config for a remote mux server reached by running
+    /// `wezterm cli proxy` over ssh
+    pub fn ssh(options: SshOptions) -> Result<Self> {
+        if options.remote_address.is_empty() {
+            anyhow::bail!("remote_address must not be empty");
+        }
+        Ok(ConnectionConfig::Ssh(Box::new(SshDomain {
+            name: format!("SSH:{}", options.remote_address),
+            remote_address: options.remote_address,
+            username: options.username,
+            no_agent_auth: options.no_agent_auth,
+            timeout: options.timeout.unwrap_or_else(config::default_read_timeout),
+            remote_wezterm_path: options.remote_wezterm_path,
+            ssh_option: options.ssh_option,
+            local_echo_threshold_ms: config::default_local_echo_threshold_ms(),
+            ..Default::default()
+        })))
+    }
+
+    /// Remote clients get no local domain id: with one, the vendored client thread
+    /// reconnects or detaches on the gui main thread when the link drops, and there is
+    /// no main thread scheduler in a python process. Without one the thread just ends,
+    /// and `Connection` reconnects on the next call like it does for unix sockets
+    pub fn connect(&self) -> Result<Client> {
+        self.connect_client(true)
+    }
+
+    /// Connect again after the link dropped. Like the vendored client's own reconnects
+    /// this never starts a unix mux server, a new one would not have the panes of the
+    /// one that went away. TLS clients never start one and SSH ones always run the
+    /// remote `wezterm cli proxy`, which starts it on the remote host
+    pub fn reconnect(&self) -> Result<Client> {
+        self.connect_client(false)
+    }
+
+    fn connect_client(&self, initial: bool) -> Result<Client> {
+        // The client reads the config too, for the default unix domain among others
+        configuration();
+        match self {
+            ConnectionConfig::Unix(options) => options.connect(initial),
+            ConnectionConfig::Tls(tls) => {
+                let mut ui = mux::connui::ConnectionUI::new_headless();
+                Client::new_tls(None, tls, &mut ui)
+            }
+            ConnectionConfig::Ssh(ssh) => {
+                let mut ui = mux::connui::ConnectionUI::new_headless();
+                Client::new_ssh(None, ssh, &mut ui)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TlsOptions {
+    pub remote_address: String,
+    pub pem_cert: Option<PathBuf>,
+    pub pem_ca: Option<PathBuf>,
+    pub pem_private_key: Option<PathBuf>,
+    pub pem_root_certs: Vec<PathBuf>,
+    /// `user@host:port` to ssh into to start the server and fetch credentials
+    pub bootstrap_via_ssh: Option<String>,
+    pub expected_cn: Option<String>,
+    pub accept_invalid_hostnames: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct SshOptions {
+    /// `host` or `host:port`
+    pub remote_address: String,
+    pub username: Option<String>,
+    pub no_agent_auth: bool,
+    pub timeout: Option<Duration>,
+    pub remote_wezterm_path: Option<String>,
+    /// Extra ssh_config options, eg. `proxycommand`
+    pub ssh_option: HashMap<String, String>,
+}
+
+fn check_file(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path)
+        .with_context(|| format!("Unable to read certificate file {}", path.display()))?;
+    if !metadata.is_file() {
+        anyhow::bail!("Certificate path {} is not a file", path.display());
+    }
+    Ok(())
+}
+
+impl UnixOptions {
+    /// Resolve the unix domain to use, `None` means let the client pick the default one
+    fn unix_domain(&self) -> Result<Option<UnixDomain>> {
+        if let Some(socket_path) = &self.socket_path {
+            return Ok(Some(UnixDomain {
+                name: "unix".to_string(),
+                socket_path: Some(socket_path.clone()),
+                no_serve_automatically: self.no_auto_start,
+                ..Default::default()
+            }));
+        }
+
+        let Some(name) = &self.domain else {
+            return Ok(None);
+        };
+        let config = configuration();
+        let domain = config
+            .unix_domains
+            .iter()
+            .find(|domain| &domain.name == name)
+            .with_context(|| format!("No unix domain named {name:?} in the wezterm config"))?;
+        Ok(Some(domain.clone()))
+    }
+
+    fn connect(&self, initial: bool) -> Result<Client> {
+        let mut ui = mux::connui::ConnectionUI::new_headless();
+        let no_auto_start = self.no_auto_start || !initial;
+        match self.unix_domain()? {
+            Some(domain) => Client::new_unix_domain(None, &domain, initial, &mut ui, no_auto_start),
+            None => Client::new_default_unix_domain(
+                initial,
+                &mut ui,
+                no_auto_start,
+                self.prefer_mux,
+                &self.class_name,
+            ),
+        }
+    }
+}
+
+/// Check that the server speaks our codec version, failing with
+/// `IncompatibleVersionError` when it does not
+async fn verify_version(client: &Client) -> Result<GetCodecVersionResponse> {
+    let ui = mux::connui::ConnectionUI::new_headless();
+    client.verify_version_compat(&ui).await
+}
+
+/// Try to connect up to `retries + 1` times, doubling the delay between attempts
+/// starting from `backoff`. `initial` is false when reconnecting, see `ConnectionConfig::reconnect`.
+/// Each attempt blocks while the socket is dialed so it is run off the executor.
+async fn connect_with_retry(
+    config: ConnectionConfig,
+    initial: bool,
+    retries: u32,
+    backoff: Duration,
+) -> Result<Client> {
+    let mut delay = backoff;
+    let mut attempt = 0;
+    loop {
+        let config = config.clone();
+        let connect = move || {
+            if initial {
+                config.connect()
+            } else {
+                config.reconnect()
+            }
+        };
+        match runtime::spawn_blocking(connect).await {
+            Ok(client) => return Ok(client),
+            Err(err) if attempt >= retries => {
+                return Err(err).context(ConnectFailed {
+                    attempts: attempt + 1,
+                })
+            }
+            Err(_) => {
+                runtime::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Attempts made to re-establish a dropped connection before giving up
+const RECONNECT_RETRIES: u32 = 5;
+const RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ConnectionState {
+    Connected,
+    Reconnecting,
+    /// The last reconnection attempt failed, the next request will try again
+    Disconnected,
+}
+
+impl ConnectionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionState::Connected => "connected",
+            ConnectionState::Reconnecting => "reconnecting",
+            ConnectionState::Disconnected => "disconnected",
+        }
+    }
+}
+
+/// Mirrors the `rpc!` macro of `wezterm_client::client::Client`, but takes a closure
+/// building the request so that it can be sent again after reconnecting
+macro_rules! rpc {
+    ($method_name:ident, $request_type:ident, $response_type:ident) => {
+        pub async fn $method_name(
+            &self,
+            request: impl Fn() -> codec::$request_type,
+        ) -> Result<codec::$response_type> {
+            match self.request(|| Pdu::$request_type(request())).await? {
+                Pdu::$response_type(response) => Ok(response),
+                pdu => anyhow::bail!("unexpected response {:?}", pdu),
+            }
+        }
+    };
+
+    ($method_name:ident, $request_type:ident = (), $response_type:ident) => {
+        pub async fn $method_name(&self) -> Result<codec::$response_type> {
+            match self
+                .request(|| Pdu::$request_type(codec::$request_type {}))
+                .await?
+            {
+                Pdu::$response_type(response) => Ok(response),
+                pdu => anyhow::bail!("unexpected response {:?}", pdu),
+            }
+        }
+    };
+}
+
+/// A `Client` that is transparently replaced when the server goes away.
+/// Cloning is cheap and clones share the same underlying client
+#[derive(Clone)]
+pub(crate) struct Connection {
+    inner: Arc<ConnectionInner>,
+}
+
+struct ConnectionInner {
+    config: ConnectionConfig,
+    strict_version: bool,
+    client_id: ClientId,
+    /// Bumped on every reconnect so concurrent failures only reconnect once
+    current: Mutex<(u64, Client)>,
+    reconnecting: async_std::sync::Mutex<()>,
+    state: Mutex<ConnectionState>,
+    on_reconnect: Mutex<Option<PyObject>>,
+}
+
+impl Connection {
+    /// Connect, retrying as configured, then register with the server
+    pub async fn establish(
+        config: ConnectionConfig,
+        strict_version: bool,
+        retries: u32,
+        backoff: Duration,
+    ) -> Result<Self> {
+        let client_id = ClientId::new();
+        let client = connect_with_retry(config.clone(), true, retries, backoff).await?;
+        handshake(&client, &client_id, strict_version).await?;
+        Ok(Self {
+            inner: Arc::new(ConnectionInner {
+                config,
+                strict_version,
+                client_id,
+                current: Mutex::new((0, client)),
+                reconnecting: async_std::sync::Mutex::new(()),
+                state: Mutex::new(ConnectionState::Connected),
+                on_reconnect: Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.inner.state.lock().unwrap()
+    }
+
+    pub fn on_reconnect(&self) -> Option<PyObject> {
+        self.inner.on_reconnect.lock().unwrap().clone()
+    }
+
+    pub fn set_on_reconnect(&self, callback: Option<PyObject>) {
+        *self.inner.on_reconnect.lock().unwrap() = callback;
+    }
+
+    fn current(&self) -> (u64, Client) {
+        self.inner.current.lock().unwrap().clone()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        *self.inner.state.lock().unwrap() = state;
+    }
+
+    /// Issue a request built by `request`, reconnecting if the server has gone away.
+    /// Requests that never left this process are retried on the new connection,
+    /// requests that were lost in flight fail since the server may have acted on them
+    async fn call<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(Client) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let (generation, client) = self.current();
+        let err = match request(client).await {
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+
+        if is_undelivered(&err) {
+            self.reconnect(generation).await.context(Disconnected)?;
+            let (_, client) = self.current();
+            return request(client).await;
+        }
+        if is_lost(&err) {
+            let err = err.context(Disconnected);
+            if let Err(reconnect_err) = self.reconnect(generation).await {
+                return Err(err.context(format!("{reconnect_err:#}")));
+            }
+            return Err(err);
+        }
+        Err(err)
+    }
+
+    /// Send the pdu built by `request`, turning `ErrorResponse` replies into errors
+    async fn request(&self, request: impl Fn() -> Pdu) -> Result<Pdu> {
+        let pdu = self
+            .call(|client| {
+                let pdu = request();
+                async move { client.send_pdu(pdu).await }
+            })
+            .await?;
+        match pdu {
+            Pdu::ErrorResponse(codec::ErrorResponse { reason }) => {
+                Err(ErrorReply { reason }.into())
+            }
+            pdu => Ok(pdu),
+        }
+    }
+
+    rpc!(list_panes, ListPanes = (), ListPanesResponse);
+    rpc!(list_clients, GetClientList = (), GetClientListResponse);
+    rpc!(get_codec_version, GetCodecVersion, GetCodecVersionResponse);
+    rpc!(set_focused_pane_id, SetFocusedPane, UnitResponse);
+    rpc!(write_to_pane, WriteToPane, UnitResponse);
+    rpc!(send_paste, SendPaste, UnitResponse);
+    rpc!(key_down, SendKeyDown, UnitResponse);
+    rpc!(kill_pane, KillPane, UnitResponse);
+    rpc!(set_window_workspace, SetWindowWorkspace, UnitResponse);
+    rpc!(rename_workspace, RenameWorkspace, UnitResponse);
+    rpc!(spawn_v2, SpawnV2, SpawnResponse);
+    rpc!(split_pane, SplitPane, SpawnResponse);
+    rpc!(set_zoomed, SetPaneZoomed, UnitResponse);
+    rpc!(adjust_pane_size, AdjustPaneSize, UnitResponse);
+    rpc!(
+        move_pane_to_new_tab,
+        MovePaneToNewTab,
+        MovePaneToNewTabResponse
+    );
+    rpc!(set_tab_title, TabTitleChanged, UnitResponse);
+    rpc!(set_window_title, WindowTitleChanged, UnitResponse);
+    rpc!(
+        get_pane_direction,
+        GetPaneDirection,
+        GetPaneDirectionResponse
+    );
+
+    pub async fn resolve_pane_id(&self, pane_id: Option<usize>) -> Result<usize> {
+        self.call(|client| async move { client.resolve_pane_id(pane_id).await })
+            .await
+    }
+
+    async fn reconnect(&self, generation: u64) -> Result<()> {
+        let _guard = self.inner.reconnecting.lock().await;
+        if self.current().0 != generation {
+            // Another request already replaced the client while we waited
+            return Ok(());
+        }
+
+        self.set_state(ConnectionState::Reconnecting);
+        let result = async {
+            let client = connect_with_retry(
+                self.inner.config.clone(),
+                false,
+                RECONNECT_RETRIES,
+                RECONNECT_BACKOFF,
+            )
+            .await?;
+            handshake(&client, &self.inner.client_id, self.inner.strict_version).await?;
+            Ok::<_, anyhow::Error>(client)
+        }
+        .await
+        .context("Failed to reconnect");
+
+        match result {
+            Ok(client) => {
+                *self.inner.current.lock().unwrap() = (generation + 1, client);
+                self.set_state(ConnectionState::Connected);
+                self.notify_reconnected();
+                Ok(())
+            }
+            Err(err) => {
+                self.set_state(ConnectionState::Disconnected);
+                Err(err)
+            }
+        }
+    }
+
+    /// Run the on_reconnect callback on the event loop when there is one,
+    /// directly otherwise
+    fn notify_reconnected(&self) {
+        let Some(callback) = self.on_reconnect() else {
+            return;
+        };
+        Python::with_gil(|py| {
+            let result = match runtime::current_event_loop(py) {
+                Some(event_loop) => event_loop
+                    .call_method1("call_soon_threadsafe", (callback,))
+                    .map(|_| ()),
+                None => callback.call0(py).map(|_| ()),
+            };
+            if let Err(err) = result {
+                err.print(py);
+            }
+        });
+    }
+}
+
+/// Register with the server, checking its codec version first if asked to
+async fn handshake(client: &Client, client_id: &ClientId, strict_version: bool) -> Result<()> {
+    if strict_version {
+        verify_version(client).await?;
+    }
+    client
+        .set_client_id(SetClientId {
+            client_id: client_id.clone(),
+        })
+        .await
+        .context("Failed to register with the server")?;
+    Ok(())
+}
+
+// The vendored client reports a dead connection with private error types and plain
+// messages, so these have to match wezterm-src/wezterm-client/src/client.rs exactly
+/// `ChannelSendError` (client.rs:39), `Client::send_pdu` could not reach the client thread
+const CHANNEL_SEND_ERROR: &str = "ChannelSendError";
+/// Context `Client::send_pdu` (client.rs:1277) adds when no response comes back
+const SEND_PDU_RECV: &str = "send_pdu recv";
+/// Reason pending requests fail with when the client thread exits (client.rs:331, 365)
+const CLIENT_WAS_DESTROYED: &str = "Client was destroyed";
+
+/// The client thread had already exited so the request was never sent
+fn is_undelivered(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| cause.to_string() == CHANNEL_SEND_ERROR)
+}
+
+/// The connection dropped while we were waiting for the response
+fn is_lost(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        let cause = cause.to_string();
+        cause == SEND_PDU_RECV || cause == CLIENT_WAS_DESTROYED
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    /// Errors shaped like the ones `Client::send_pdu` returns
+    fn send_pdu_error(cause: anyhow::Error, context: &'static str) -> anyhow::Error {
+        Err::<(), _>(cause).context(context).unwrap_err()
+    }
+
+    #[test]
+    fn undelivered_requests_are_retried() {
+        let err = send_pdu_error(anyhow!(CHANNEL_SEND_ERROR), "send_pdu send");
+        assert!(is_undelivered(&err));
+        assert!(!is_lost(&err));
+        assert!(is_undelivered(&err.context("Failed to set pane focus")));
+    }
+
+    #[test]
+    fn requests_lost_in_flight_are_not_retried() {
+        let closed = send_pdu_error(anyhow!("receiving on a closed channel"), SEND_PDU_RECV);
+        assert!(is_lost(&closed));
+        assert!(!is_undelivered(&closed));
+
+        let destroyed = anyhow!(CLIENT_WAS_DESTROYED).context("Couldn't fetch panes from wezterm");
+        assert!(is_lost(&destroyed));
+        assert!(!is_undelivered(&destroyed));
+    }
+
+    #[test]
+    fn other_errors_are_left_alone() {
+        let err = anyhow!(ErrorReply {
+            reason: "Error: pane 3 not found".to_string()
+        });
+        assert!(!is_undelivered(&err));
+        assert!(!is_lost(&err));
+        assert!(!is_lost(&anyhow!("{SEND_PDU_RECV} failed")));
+    }
+
+    #[test]
+    fn unix_domains_come_from_the_config_file() {
+        let dir = std::env::temp_dir().join(format!("wezpy-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config_file = dir.join("wezterm.lua");
+        let socket_path = dir.join("work.sock");
+        std::fs::write(
+            &config_file,
+            format!(
+                "return {{ unix_domains = {{ {{ name = 'work', socket_path = '{}' }} }} }}",
+                socket_path.display()
+            ),
+        )
+        .unwrap();
+        std::env::set_var("WEZTERM_CONFIG_FILE", &config_file);
+
+        let options = UnixOptions {
+            socket_path: None,
+            domain: Some("work".to_string()),
+            class_name: wezterm_gui_subcommands::DEFAULT_WINDOW_CLASS.to_string(),
+            prefer_mux: false,
+            no_auto_start: true,
+        };
+        let domain = options.unix_domain().unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(domain.name, "work");
+        assert_eq!(domain.socket_path, Some(socket_path));
+    }
+}
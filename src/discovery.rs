@@ -0,0 +1,133 @@
+use crate::connection::ConnectionConfig;
+use pyo3::prelude::*;
+use std::{collections::HashMap, path::PathBuf};
+
+/// A wezterm gui instance found in the runtime directory
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct GuiInstance {
+    socket_path: PathBuf,
+    /// Class the gui published its socket under, see `wezterm start --class`
+    class_name: Option<String>,
+    /// Parsed from the `gui-sock-<pid>` socket name
+    pid: Option<u32>,
+    /// False when the socket is stale, ie. nothing is listening on it anymore
+    reachable: bool,
+}
+
+#[pymethods]
+impl GuiInstance {
+    /// Connect a `WeztermClient` to this instance, see `WeztermClient.connect`
+    #[pyo3(signature = (retries=0, backoff=0.1, timeout=None, strict_version=false, blocking=false))]
+    fn connect<'a>(
+        &self,
+        py: Python<'a>,
+        retries: u32,
+        backoff: f64,
+        timeout: Option<f64>,
+        strict_version: bool,
+        blocking: bool,
+    ) -> PyResult<&'a PyAny> {
+        let config = ConnectionConfig::unix(
+            Some(self.socket_path.clone()),
+            self.class_name.clone(),
+            false,
+            true,
+            None,
+        );
+        crate::connect_py(
+            py,
+            config,
+            retries,
+            backoff,
+            timeout,
+            strict_version,
+            blocking,
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GuiInstance(socket_path={:?}, class_name={:?}, pid={:?}, reachable={})",
+            self.socket_path, self.class_name, self.pid, self.reachable
+        )
+    }
+}
+
+/// List the gui instances on this machine, eldest first.
+/// Sockets published for a class name but no longer listening are included as unreachable
+#[pyfunction]
+pub(crate) fn discover() -> Vec<GuiInstance> {
+    let mut class_names = published_class_names();
+    let mut socks = wezterm_client::discovery::discover_gui_socks();
+    for sock in class_names.keys() {
+        if !socks.contains(sock) {
+            socks.push(sock.clone());
+        }
+    }
+
+    socks
+        .into_iter()
+        .map(|socket_path| GuiInstance {
+            class_name: class_names.remove(&socket_path),
+            pid: socket_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix("gui-sock-"))
+                .and_then(|pid| pid.parse().ok()),
+            reachable: is_reachable(&socket_path),
+            socket_path,
+        })
+        .collect()
+}
+
+/// Map socket paths to the class names they were published under for the current display.
+/// Mirrors the symlink naming used by `wezterm_client::discovery::publish_gui_sock_path`
+#[cfg(unix)]
+fn published_class_names() -> HashMap<PathBuf, String> {
+    let mut prefixes = vec![];
+    if cfg!(target_os = "macos") {
+        prefixes.push("default-".to_string());
+    } else {
+        if let Ok(wayland) = std::env::var("WAYLAND_DISPLAY") {
+            prefixes.push(format!("wayland-{wayland}-"));
+        }
+        let x11 = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".to_string());
+        prefixes.push(format!("x11-{x11}-"));
+    }
+
+    let mut class_names = HashMap::new();
+    let Ok(dir) = std::fs::read_dir(&*config::RUNTIME_DIR) else {
+        return class_names;
+    };
+    for entry in dir.flatten() {
+        let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        let Some(class_name) = prefixes
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix.as_str()))
+        else {
+            continue;
+        };
+        if let Ok(target) = std::fs::read_link(entry.path()) {
+            class_names.insert(target, class_name.to_string());
+        }
+    }
+    class_names
+}
+
+#[cfg(not(unix))]
+fn published_class_names() -> HashMap<PathBuf, String> {
+    HashMap::new()
+}
+
+#[cfg(unix)]
+fn is_reachable(sock: &std::path::Path) -> bool {
+    std::os::unix::net::UnixStream::connect(sock).is_ok()
+}
+
+#[cfg(not(unix))]
+fn is_reachable(sock: &std::path::Path) -> bool {
+    sock.exists()
+}
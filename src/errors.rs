@@ -0,0 +1,213 @@
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use std::fmt;
+use wezterm_client::client::IncompatibleVersionError;
+
+create_exception!(
+    wezpy,
+    WeztermError,
+    PyException,
+    "Base class for every error raised by wezpy"
+);
+create_exception!(
+    wezpy,
+    ConnectError,
+    WeztermError,
+    "Raised when wezterm could not be reached"
+);
+create_exception!(
+    wezpy,
+    ConnectionLost,
+    WeztermError,
+    "Raised when the connection dropped and could not be re-established in time"
+);
+create_exception!(
+    wezpy,
+    PaneNotFound,
+    WeztermError,
+    "Raised when a pane id does not refer to an existing pane"
+);
+create_exception!(
+    wezpy,
+    ServerError,
+    WeztermError,
+    "Raised when the server answered with an error, see the `reason` attribute"
+);
+create_exception!(
+    wezpy,
+    InvalidPattern,
+    WeztermError,
+    "Raised when a regular expression fails to compile"
+);
+create_exception!(
+    wezpy,
+    Timeout,
+    WeztermError,
+    "Raised when an operation did not complete in time"
+);
+create_exception!(
+    wezpy,
+    ProtocolMismatch,
+    WeztermError,
+    "Raised when the server speaks a different codec version than wezpy"
+);
+
+pub(crate) fn register(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("WeztermError", py.get_type::<WeztermError>())?;
+    m.add("ConnectError", py.get_type::<ConnectError>())?;
+    m.add("ConnectionLost", py.get_type::<ConnectionLost>())?;
+    m.add("PaneNotFound", py.get_type::<PaneNotFound>())?;
+    m.add("ServerError", py.get_type::<ServerError>())?;
+    m.add("InvalidPattern", py.get_type::<InvalidPattern>())?;
+    m.add("Timeout", py.get_type::<Timeout>())?;
+    m.add("ProtocolMismatch", py.get_type::<ProtocolMismatch>())?;
+    Ok(())
+}
+
+/// The server replied with an `ErrorResponse`
+#[derive(Debug)]
+pub(crate) struct ErrorReply {
+    pub reason: String,
+}
+
+impl fmt::Display for ErrorReply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wezterm replied with an error: {}", self.reason)
+    }
+}
+
+impl std::error::Error for ErrorReply {}
+
+impl ErrorReply {
+    /// The server has no dedicated error for unknown panes, only these messages,
+    /// which the mux server prefixes with "Error: "
+    fn is_missing_pane(&self) -> bool {
+        let reason = self.reason.strip_prefix("Error: ").unwrap_or(&self.reason);
+        reason.starts_with("no such pane")
+            || (reason.starts_with("pane ") && reason.ends_with(" not found"))
+            || (reason.starts_with("pane_id ") && reason.ends_with(" invalid"))
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct NoSuchPane(pub usize);
+
+impl fmt::Display for NoSuchPane {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No pane with id {}", self.0)
+    }
+}
+
+impl std::error::Error for NoSuchPane {}
+
+#[derive(Debug)]
+pub(crate) struct Disconnected;
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Lost connection to wezterm")
+    }
+}
+
+impl std::error::Error for Disconnected {}
+
+#[derive(Debug)]
+pub(crate) struct ConnectFailed {
+    pub attempts: u32,
+}
+
+impl fmt::Display for ConnectFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unable to connect to wezterm after {} attempts",
+            self.attempts
+        )
+    }
+}
+
+impl std::error::Error for ConnectFailed {}
+
+#[derive(Debug)]
+pub(crate) struct TimedOut(pub std::time::Duration);
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+/// Pick the python exception for `err`, the most specific cause wins.
+/// The message is the whole context chain and `__cause__` walks the same chain
+pub(crate) fn to_py_err(err: anyhow::Error) -> PyErr {
+    Python::with_gil(|py| {
+        let message = format!("{err:#}");
+        let py_err = if err.is::<Disconnected>() {
+            ConnectionLost::new_err(message)
+        } else if err.is::<TimedOut>() {
+            Timeout::new_err(message)
+        } else if err.is::<IncompatibleVersionError>() {
+            ProtocolMismatch::new_err(message)
+        } else if err.is::<ConnectFailed>() {
+            ConnectError::new_err(message)
+        } else if err.is::<NoSuchPane>() {
+            PaneNotFound::new_err(message)
+        } else if let Some(reply) = err.downcast_ref::<ErrorReply>() {
+            if reply.is_missing_pane() {
+                PaneNotFound::new_err(message)
+            } else {
+                let py_err = ServerError::new_err(message);
+                if let Err(err) = py_err.value(py).setattr("reason", &reply.reason) {
+                    return err;
+                }
+                py_err
+            }
+        } else if err.is::<regex::Error>() {
+            InvalidPattern::new_err(message)
+        } else {
+            WeztermError::new_err(message)
+        };
+
+        let cause = err
+            .chain()
+            .skip(1)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .fold(None, |cause, source| {
+                let py_err = WeztermError::new_err(source.to_string());
+                py_err.set_cause(py, cause);
+                Some(py_err)
+            });
+        py_err.set_cause(py, cause);
+        py_err
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_missing_pane(reason: &str) -> bool {
+        ErrorReply {
+            reason: reason.to_string(),
+        }
+        .is_missing_pane()
+    }
+
+    #[test]
+    fn recognises_missing_pane_replies() {
+        assert!(is_missing_pane("Error: pane 3 not found"));
+        assert!(is_missing_pane("Error: pane_id 3 invalid"));
+        assert!(is_missing_pane("Error: no such pane 3"));
+        assert!(is_missing_pane("pane 3 not found"));
+    }
+
+    #[test]
+    fn leaves_other_replies_alone() {
+        assert!(!is_missing_pane("Error: window 3 is invalid"));
+        assert!(!is_missing_pane("Error: tab 3 not found"));
+        assert!(!is_missing_pane("Error: Failed to retrieve tab with ID 3"));
+    }
+}
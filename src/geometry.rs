@@ -0,0 +1,168 @@
+use crate::pane::Pane;
+use pyo3::prelude::*;
+
+/// Where a pane sits within its tab, right and bottom are exclusive.
+/// Pixel values are zero when the server does not know the pixel size
+#[pyclass(get_all)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Rect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    pixel_left: usize,
+    pixel_top: usize,
+    pixel_width: usize,
+    pixel_height: usize,
+}
+
+#[pymethods]
+impl Rect {
+    #[getter]
+    fn right(&self) -> usize {
+        self.left + self.width
+    }
+
+    #[getter]
+    fn bottom(&self) -> usize {
+        self.top + self.height
+    }
+
+    /// Whether the cell at `col`, `row` of the tab belongs to this rect
+    pub(crate) fn contains(&self, col: usize, row: usize) -> bool {
+        (self.left..self.right()).contains(&col) && (self.top..self.bottom()).contains(&row)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Rect(left={}, top={}, width={}, height={})",
+            self.left, self.top, self.width, self.height
+        )
+    }
+}
+
+impl Rect {
+    pub(crate) fn of(pane: &Pane) -> Self {
+        let size = &pane.size;
+        let cell_width = size.pixel_width.checked_div(size.cols).unwrap_or(0);
+        let cell_height = size.pixel_height.checked_div(size.rows).unwrap_or(0);
+        Self {
+            left: pane.left_col,
+            top: pane.top_row,
+            width: size.cols,
+            height: size.rows,
+            pixel_left: pane.left_col * cell_width,
+            pixel_top: pane.top_row * cell_height,
+            pixel_width: size.pixel_width,
+            pixel_height: size.pixel_height,
+        }
+    }
+
+    fn overlaps_columns(&self, other: &Self) -> bool {
+        self.left < other.right() && other.left < self.right()
+    }
+
+    fn overlaps_rows(&self, other: &Self) -> bool {
+        self.top < other.bottom() && other.top < self.bottom()
+    }
+}
+
+/// The panes sharing an edge with a pane, panes are separated by a one cell wide split
+#[pyclass(get_all)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Neighbors {
+    left: Vec<Pane>,
+    right: Vec<Pane>,
+    up: Vec<Pane>,
+    down: Vec<Pane>,
+}
+
+#[pymethods]
+impl Neighbors {
+    fn __repr__(&self) -> String {
+        let ids = |panes: &[Pane]| panes.iter().map(|pane| pane.pane_id).collect::<Vec<_>>();
+        format!(
+            "Neighbors(left={:?}, right={:?}, up={:?}, down={:?})",
+            ids(&self.left),
+            ids(&self.right),
+            ids(&self.up),
+            ids(&self.down)
+        )
+    }
+}
+
+/// The neighbors of `pane` among `panes`, which may include panes of other tabs
+pub(crate) fn neighbors(pane: &Pane, panes: Vec<Pane>) -> Neighbors {
+    let rect = Rect::of(pane);
+    let mut neighbors = Neighbors::default();
+    for other in panes {
+        if other.tab_id != pane.tab_id || other.pane_id == pane.pane_id {
+            continue;
+        }
+        let other_rect = Rect::of(&other);
+        if rect.overlaps_rows(&other_rect) {
+            if other_rect.right() + 1 == rect.left {
+                neighbors.left.push(other);
+            } else if rect.right() + 1 == other_rect.left {
+                neighbors.right.push(other);
+            }
+        } else if rect.overlaps_columns(&other_rect) {
+            if other_rect.bottom() + 1 == rect.top {
+                neighbors.up.push(other);
+            } else if rect.bottom() + 1 == other_rect.top {
+                neighbors.down.push(other);
+            }
+        }
+    }
+    neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pane::test_entry;
+
+    fn ids(panes: &[Pane]) -> Vec<usize> {
+        panes.iter().map(|pane| pane.pane_id).collect()
+    }
+
+    #[test]
+    fn rect_contains_its_cells_only() {
+        let rect = Rect::of(&Pane::from(&test_entry(0, 0, 41, 0, 39, 24)));
+        assert_eq!((rect.right(), rect.bottom()), (80, 24));
+        assert!(rect.contains(41, 0));
+        assert!(rect.contains(79, 23));
+        assert!(!rect.contains(40, 0));
+        assert!(!rect.contains(80, 0));
+        assert!(!rect.contains(41, 24));
+    }
+
+    #[test]
+    fn neighbors_share_an_edge_across_the_split() {
+        // 0 | 1
+        // --+ 1
+        // 2 | 1
+        let panes: Vec<Pane> = [
+            test_entry(0, 0, 0, 0, 40, 12),
+            test_entry(0, 1, 41, 0, 39, 24),
+            test_entry(0, 2, 0, 13, 40, 11),
+            test_entry(1, 3, 0, 0, 80, 24),
+        ]
+        .iter()
+        .map(Pane::from)
+        .collect();
+
+        let of_0 = neighbors(&panes[0], panes.clone());
+        assert_eq!(ids(&of_0.right), [1]);
+        assert_eq!(ids(&of_0.down), [2]);
+        assert!(of_0.left.is_empty() && of_0.up.is_empty());
+
+        let of_1 = neighbors(&panes[1], panes.clone());
+        assert_eq!(ids(&of_1.left), [0, 2]);
+        assert!(of_1.right.is_empty() && of_1.up.is_empty() && of_1.down.is_empty());
+
+        let of_3 = neighbors(&panes[3], panes.clone());
+        assert!(of_3.left.is_empty() && of_3.right.is_empty());
+        assert!(of_3.up.is_empty() && of_3.down.is_empty());
+    }
+}
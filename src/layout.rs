@@ -0,0 +1,242 @@
+use crate::pane::{Pane, PaneSize};
+use mux::tab::{PaneNode, SplitDirection};
+use pyo3::prelude::*;
+
+/// The split tree of a tab
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Layout {
+    window_id: usize,
+    tab_id: usize,
+    tab_title: String,
+    /// Either a `Split` or the `Pane` filling the whole tab
+    root: LayoutNode,
+}
+
+#[pymethods]
+impl Layout {
+    /// The leaf panes, first to second child, ie. left to right and top to bottom
+    fn panes(&self) -> Vec<Pane> {
+        let mut panes = vec![];
+        self.root.collect_panes(&mut panes);
+        panes
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Layout(window_id={}, tab_id={}, tab_title={:?})",
+            self.window_id, self.tab_id, self.tab_title
+        )
+    }
+}
+
+impl Layout {
+    /// None for a tab without panes
+    pub(crate) fn new(node: &PaneNode, tab_title: String) -> Option<Self> {
+        let root = LayoutNode::new(node)?;
+        let first = root.first_pane();
+        Some(Self {
+            window_id: first.window_id,
+            tab_id: first.tab_id,
+            tab_title,
+            root,
+        })
+    }
+
+    pub(crate) fn contains(&self, pane_id: usize) -> bool {
+        self.root.contains(pane_id)
+    }
+
+    /// The innermost split containing `pane_id`, limited to splits in `direction` when given,
+    /// along with whether the pane is on its first side
+    pub(crate) fn enclosing_split(
+        &self,
+        pane_id: usize,
+        direction: Option<&str>,
+    ) -> Option<(&Split, bool)> {
+        self.root.enclosing_split(pane_id, direction)
+    }
+}
+
+/// A tab area divided in two
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Split {
+    /// "horizontal" when `first` is left of `second`, "vertical" when it is above
+    pub direction: &'static str,
+    pub first_size: PaneSize,
+    pub second_size: PaneSize,
+    /// Either a `Split` or a `Pane`
+    first: LayoutNode,
+    /// Either a `Split` or a `Pane`
+    second: LayoutNode,
+}
+
+#[pymethods]
+impl Split {
+    fn __repr__(&self) -> String {
+        format!(
+            "Split(direction={:?}, first_size={}x{}, second_size={}x{})",
+            self.direction,
+            self.first_size.cols,
+            self.first_size.rows,
+            self.second_size.cols,
+            self.second_size.rows
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) enum LayoutNode {
+    Split(Box<Split>),
+    Pane(Box<Pane>),
+}
+
+impl LayoutNode {
+    fn new(node: &PaneNode) -> Option<Self> {
+        match node {
+            PaneNode::Empty => None,
+            PaneNode::Leaf(entry) => Some(Self::Pane(Box::new(entry.into()))),
+            PaneNode::Split { left, right, node } => match (Self::new(left), Self::new(right)) {
+                (Some(first), Some(second)) => Some(Self::Split(Box::new(Split {
+                    direction: match node.direction {
+                        SplitDirection::Horizontal => "horizontal",
+                        SplitDirection::Vertical => "vertical",
+                    },
+                    first_size: node.first.into(),
+                    second_size: node.second.into(),
+                    first,
+                    second,
+                }))),
+                (first, second) => first.or(second),
+            },
+        }
+    }
+
+    fn first_pane(&self) -> &Pane {
+        match self {
+            Self::Split(split) => split.first.first_pane(),
+            Self::Pane(pane) => pane,
+        }
+    }
+
+    fn contains(&self, pane_id: usize) -> bool {
+        match self {
+            Self::Split(split) => split.first.contains(pane_id) || split.second.contains(pane_id),
+            Self::Pane(pane) => pane.pane_id == pane_id,
+        }
+    }
+
+    fn enclosing_split(&self, pane_id: usize, direction: Option<&str>) -> Option<(&Split, bool)> {
+        let Self::Split(split) = self else {
+            return None;
+        };
+        let (child, in_first) = if split.first.contains(pane_id) {
+            (&split.first, true)
+        } else if split.second.contains(pane_id) {
+            (&split.second, false)
+        } else {
+            return None;
+        };
+        child.enclosing_split(pane_id, direction).or_else(|| {
+            direction
+                .is_none_or(|direction| direction == split.direction)
+                .then_some((&**split, in_first))
+        })
+    }
+
+    fn collect_panes(&self, panes: &mut Vec<Pane>) {
+        match self {
+            Self::Split(split) => {
+                split.first.collect_panes(panes);
+                split.second.collect_panes(panes);
+            }
+            Self::Pane(pane) => panes.push((**pane).clone()),
+        }
+    }
+}
+
+impl IntoPy<PyObject> for LayoutNode {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        match self {
+            Self::Split(split) => (*split).into_py(py),
+            Self::Pane(pane) => (*pane).into_py(py),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pane::test_entry;
+    use mux::tab::{PaneEntry, SplitDirectionAndSize};
+
+    fn split(direction: SplitDirection, left: PaneNode, right: PaneNode) -> PaneNode {
+        let size = |node: &PaneNode| match node {
+            PaneNode::Leaf(entry) => entry.size,
+            _ => Default::default(),
+        };
+        PaneNode::Split {
+            node: SplitDirectionAndSize {
+                direction,
+                first: size(&left),
+                second: size(&right),
+            },
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn pane_ids(layout: &Layout) -> Vec<usize> {
+        layout.panes().iter().map(|pane| pane.pane_id).collect()
+    }
+
+    fn in_window(window_id: usize, mut entry: PaneEntry) -> PaneEntry {
+        entry.window_id = window_id;
+        entry
+    }
+
+    #[test]
+    fn empty_sides_collapse_to_the_other_side() {
+        let tab = split(
+            SplitDirection::Horizontal,
+            PaneNode::Empty,
+            PaneNode::Leaf(test_entry(0, 5, 0, 0, 80, 24)),
+        );
+        let layout = Layout::new(&tab, "tab".to_string()).unwrap();
+        assert!(matches!(&layout.root, LayoutNode::Pane(pane) if pane.pane_id == 5));
+
+        let tab = split(
+            SplitDirection::Vertical,
+            split(
+                SplitDirection::Horizontal,
+                PaneNode::Leaf(test_entry(0, 1, 0, 0, 40, 24)),
+                PaneNode::Leaf(test_entry(0, 2, 41, 0, 39, 24)),
+            ),
+            PaneNode::Empty,
+        );
+        let layout = Layout::new(&tab, "tab".to_string()).unwrap();
+        assert!(
+            matches!(&layout.root, LayoutNode::Split(split) if split.direction == "horizontal")
+        );
+        assert_eq!(pane_ids(&layout), [1, 2]);
+
+        assert!(Layout::new(&PaneNode::Empty, "tab".to_string()).is_none());
+    }
+
+    #[test]
+    fn ids_come_from_the_first_leaf() {
+        let tab = split(
+            SplitDirection::Horizontal,
+            PaneNode::Empty,
+            split(
+                SplitDirection::Vertical,
+                PaneNode::Leaf(in_window(3, test_entry(7, 1, 0, 0, 80, 12))),
+                PaneNode::Leaf(in_window(4, test_entry(8, 2, 0, 13, 80, 11))),
+            ),
+        );
+        let layout = Layout::new(&tab, "tab".to_string()).unwrap();
+        assert_eq!((layout.window_id, layout.tab_id), (3, 7));
+        assert_eq!(pane_ids(&layout), [1, 2]);
+    }
+}
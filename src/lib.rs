@@ -1,35 +1,99 @@
 #![allow(non_local_definitions)]
 
 use anyhow::{Context, Result};
-use async_std::prelude::FutureExt;
 use codec::{InputSerial, KillPane, SendKeyDown};
-use config::keyassignment::PaneDirection;
-use mux::tab::PaneEntry;
+use config::keyassignment::{PaneDirection, SpawnTabDomain};
+use mux::tab::{PaneEntry, SplitRequest, SplitSize};
 use regex::Regex;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 use termwiz::input::{KeyCode, KeyEvent};
-use wezterm_client::client::Client;
-use wezterm_gui_subcommands;
 
-use pyo3::{exceptions::PyValueError, prelude::*};
+mod client_info;
+mod connection;
+mod discovery;
+mod errors;
+mod geometry;
+mod layout;
+mod pane;
+mod runtime;
+mod server_info;
+mod snapshot;
+mod spawn;
+mod split;
+mod window;
+use client_info::ClientInfo;
+use connection::{Connection, ConnectionConfig, SshOptions, TlsOptions};
+use errors::{to_py_err, NoSuchPane, TimedOut};
+use geometry::{Neighbors, Rect};
+use layout::{Layout, Split};
+use pane::{CursorPosition, Pane, PaneId, PaneSize};
+use server_info::ServerInfo;
+use snapshot::{Snapshot, SnapshotDiff};
+use spawn::{SpawnCommand, Spawned};
+use split::SplitSizeArg;
+use window::{Tab, Window};
+
+use pyo3::{
+    exceptions::{PyRuntimeWarning, PyValueError},
+    prelude::*,
+};
 
 #[pymodule]
-fn wezpy(_py: Python, m: &PyModule) -> PyResult<()> {
+fn wezpy(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<WeztermClient>()?;
+    m.add_class::<discovery::GuiInstance>()?;
+    m.add_function(wrap_pyfunction!(discovery::discover, m)?)?;
+    m.add_class::<ServerInfo>()?;
+    m.add_class::<ClientInfo>()?;
+    m.add_class::<Pane>()?;
+    m.add_class::<PaneSize>()?;
+    m.add_class::<CursorPosition>()?;
+    m.add_class::<Rect>()?;
+    m.add_class::<Neighbors>()?;
+    m.add_class::<Layout>()?;
+    m.add_class::<Split>()?;
+    m.add_class::<Window>()?;
+    m.add_class::<Tab>()?;
+    m.add_class::<Spawned>()?;
+    m.add_class::<Snapshot>()?;
+    m.add_class::<SnapshotDiff>()?;
+    m.add_function(wrap_pyfunction!(snapshot::diff, m)?)?;
+    errors::register(py, m)?;
     Ok(())
 }
 
 #[pyclass]
 #[derive(Clone)]
 struct WeztermClient {
-    connection: Client,
+    connection: Connection,
     compiled_regexs: Arc<async_std::sync::RwLock<BTreeMap<String, Arc<Regex>>>>,
+    /// Methods block until done instead of returning awaitables
+    blocking: bool,
 }
 
 impl WeztermClient {
+    fn with_connection(connection: Connection, blocking: bool) -> Self {
+        Self {
+            connection,
+            compiled_regexs: Arc::new(async_std::sync::RwLock::new(BTreeMap::new())),
+            blocking,
+        }
+    }
+
+    fn run<'a, F, T>(&self, py: Python<'a>, future: F) -> PyResult<&'a PyAny>
+    where
+        F: Future<Output = PyResult<T>> + Send + 'static,
+        T: IntoPy<PyObject> + Send + 'static,
+    {
+        run_py(py, self.blocking, future)
+    }
+
     /// Get the compiled regular expression for a given pattern, caching it if not prevoiusly present
     async fn get_regex(&self, pattern: Option<String>) -> Result<Option<Arc<Regex>>> {
         let Some(pattern) = pattern else {
@@ -47,28 +111,271 @@ impl WeztermClient {
             .write()
             .await
             .insert(pattern.to_string(), regex_ptr.clone());
-        return Ok(Some(regex_ptr));
+        Ok(Some(regex_ptr))
     }
 }
 
 #[pymethods]
 impl WeztermClient {
+    /// Connect over a unix socket, `socket_path` takes precedence over `domain`,
+    /// otherwise the default domain is chosen the same way `wezterm cli` does.
+    /// With `strict_version` a server speaking another codec version raises `ProtocolMismatch`.
+    /// With `blocking` every method runs to completion instead of returning an awaitable
     #[new]
-    fn new() -> Self {
-        let mut ui = mux::connui::ConnectionUI::new_headless();
-        let client = Client::new_default_unix_domain(
-            true,
-            &mut ui,
-            false,
-            true,
-            wezterm_gui_subcommands::DEFAULT_WINDOW_CLASS,
+    #[pyo3(signature = (socket_path=None, class_name=None, prefer_mux=true, no_auto_start=false, domain=None, strict_version=false, blocking=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        socket_path: Option<PathBuf>,
+        class_name: Option<String>,
+        prefer_mux: bool,
+        no_auto_start: bool,
+        domain: Option<String>,
+        strict_version: bool,
+        blocking: bool,
+    ) -> PyResult<Self> {
+        let config =
+            ConnectionConfig::unix(socket_path, class_name, prefer_mux, no_auto_start, domain);
+        let connection = py
+            .allow_threads(|| {
+                runtime::block_on(Connection::establish(
+                    config,
+                    strict_version,
+                    0,
+                    Duration::ZERO,
+                ))
+            })
+            .map_err(to_py_err)?;
+        Ok(Self::with_connection(connection, blocking))
+    }
+
+    /// Alternative to the constructor that retries failed connection attempts,
+    /// waiting `backoff` seconds before the first retry and doubling the wait each time.
+    /// Raises `wezpy.Timeout`, not the builtin `TimeoutError`, if no connection
+    /// was made within `timeout` seconds.
+    /// Returns an awaitable unless `blocking` is set
+    #[staticmethod]
+    #[pyo3(signature = (socket_path=None, class_name=None, prefer_mux=true, no_auto_start=false, domain=None, retries=0, backoff=0.1, timeout=None, strict_version=false, blocking=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn connect(
+        py: Python<'_>,
+        socket_path: Option<PathBuf>,
+        class_name: Option<String>,
+        prefer_mux: bool,
+        no_auto_start: bool,
+        domain: Option<String>,
+        retries: u32,
+        backoff: f64,
+        timeout: Option<f64>,
+        strict_version: bool,
+        blocking: bool,
+    ) -> PyResult<&PyAny> {
+        let config =
+            ConnectionConfig::unix(socket_path, class_name, prefer_mux, no_auto_start, domain);
+        connect_py(
+            py,
+            config,
+            retries,
+            backoff,
+            timeout,
+            strict_version,
+            blocking,
         )
-        .expect("Unable to connect to wezterm, is it installed and running?");
+    }
 
-        Self {
-            connection: client,
-            compiled_regexs: Arc::new(async_std::sync::RwLock::new(BTreeMap::new())),
-        }
+    /// Connect to a remote mux server over TLS.
+    /// Certificate paths are checked up front and raise `ValueError` when missing
+    #[staticmethod]
+    #[pyo3(signature = (remote_address, pem_cert=None, pem_ca=None, pem_private_key=None, pem_root_certs=Vec::new(), bootstrap_via_ssh=None, expected_cn=None, accept_invalid_hostnames=false, retries=0, backoff=0.1, timeout=None, strict_version=false, blocking=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn connect_tls(
+        py: Python<'_>,
+        remote_address: String,
+        pem_cert: Option<PathBuf>,
+        pem_ca: Option<PathBuf>,
+        pem_private_key: Option<PathBuf>,
+        pem_root_certs: Vec<PathBuf>,
+        bootstrap_via_ssh: Option<String>,
+        expected_cn: Option<String>,
+        accept_invalid_hostnames: bool,
+        retries: u32,
+        backoff: f64,
+        timeout: Option<f64>,
+        strict_version: bool,
+        blocking: bool,
+    ) -> PyResult<&PyAny> {
+        let config = ConnectionConfig::tls(TlsOptions {
+            remote_address,
+            pem_cert,
+            pem_ca,
+            pem_private_key,
+            pem_root_certs,
+            bootstrap_via_ssh,
+            expected_cn,
+            accept_invalid_hostnames,
+        })
+        .map_err(|err| PyValueError::new_err(format!("{err:#}")))?;
+        connect_py(
+            py,
+            config,
+            retries,
+            backoff,
+            timeout,
+            strict_version,
+            blocking,
+        )
+    }
+
+    /// Connect to a remote mux server by running `wezterm cli proxy` over ssh.
+    /// `ssh_option` is merged into the ssh_config for the host, so a `proxycommand`
+    /// can be used to reach a mux server without a real sshd
+    #[staticmethod]
+    #[pyo3(signature = (remote_address, username=None, no_agent_auth=false, ssh_timeout=None, remote_wezterm_path=None, ssh_option=HashMap::new(), retries=0, backoff=0.1, timeout=None, strict_version=false, blocking=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn connect_ssh(
+        py: Python<'_>,
+        remote_address: String,
+        username: Option<String>,
+        no_agent_auth: bool,
+        ssh_timeout: Option<f64>,
+        remote_wezterm_path: Option<String>,
+        ssh_option: HashMap<String, String>,
+        retries: u32,
+        backoff: f64,
+        timeout: Option<f64>,
+        strict_version: bool,
+        blocking: bool,
+    ) -> PyResult<&PyAny> {
+        let ssh_timeout = ssh_timeout
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|err| PyValueError::new_err(format!("Invalid ssh_timeout: {err}")))?;
+        let config = ConnectionConfig::ssh(SshOptions {
+            remote_address,
+            username,
+            no_agent_auth,
+            timeout: ssh_timeout,
+            remote_wezterm_path,
+            ssh_option,
+        })
+        .map_err(|err| PyValueError::new_err(format!("{err:#}")))?;
+        connect_py(
+            py,
+            config,
+            retries,
+            backoff,
+            timeout,
+            strict_version,
+            blocking,
+        )
+    }
+
+    /// One of "connected", "reconnecting" or "disconnected"
+    #[getter]
+    fn connection_state(&self) -> &'static str {
+        self.connection.state().as_str()
+    }
+
+    /// Called without arguments after the connection was re-established,
+    /// on the event loop when there is one.
+    /// Pane ids from before a server restart are unlikely to still be valid
+    #[getter]
+    fn get_on_reconnect(&self) -> Option<PyObject> {
+        self.connection.on_reconnect()
+    }
+
+    #[setter]
+    fn set_on_reconnect(&self, callback: Option<PyObject>) {
+        self.connection.set_on_reconnect(callback);
+    }
+
+    fn server_info<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            server_info(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// The pane of `tab_id` covering the cell at `col`, `row`, None when it is a split
+    fn pane_at<'a>(
+        &self,
+        py: Python<'a>,
+        tab_id: usize,
+        col: usize,
+        row: usize,
+    ) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            pane_at(&client, tab_id, col, row).await.map_err(to_py_err)
+        })
+    }
+
+    /// Every pane adjacent to `pane_id` on each edge, `pane_id` defaults to the current pane
+    #[pyo3(signature = (pane_id=None))]
+    fn neighbors<'a>(&self, py: Python<'a>, pane_id: Option<PaneId>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            neighbors(&client, pane_id.map(|pane| pane.0))
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Every client attached to the server, this one included
+    fn list_clients<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_clients(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// Every pane of every window, in the order the server lists them
+    fn list_panes<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_panes(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// Every gui window with its tabs
+    fn list_windows<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_windows(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// The tabs of every window, grouped by window
+    fn list_tabs<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_tabs(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// Windows, tabs, panes and layouts all from the same listing, compare them with `wezpy.diff`
+    fn snapshot<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            snapshot(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// The split tree of every tab
+    fn list_layouts<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_layouts(&client).await.map_err(to_py_err)
+        })
     }
 
     fn find_pane<'a>(
@@ -79,11 +386,58 @@ impl WeztermClient {
         title_pattern: Option<String>,
     ) -> Result<&'a PyAny, pyo3::PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let c = client;
             find_pane(&c, workspace_pattern, tab_pattern, title_pattern)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Every pane matching all of the given criteria, patterns are regular expressions.
+    /// `cwd_pattern` is matched against the path of the working directory
+    #[pyo3(signature = (workspace_pattern=None, tab_pattern=None, title_pattern=None, cwd_pattern=None, tty_name=None, window_id=None, tab_id=None, is_active=None, is_zoomed=None, window_title_pattern=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn find_panes<'a>(
+        &self,
+        py: Python<'a>,
+        workspace_pattern: Option<String>,
+        tab_pattern: Option<String>,
+        title_pattern: Option<String>,
+        cwd_pattern: Option<String>,
+        tty_name: Option<String>,
+        window_id: Option<usize>,
+        tab_id: Option<usize>,
+        is_active: Option<bool>,
+        is_zoomed: Option<bool>,
+        window_title_pattern: Option<String>,
+    ) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            let [workspace, tab_title, title, cwd, window_title] = futures::future::join_all(vec![
+                client.get_regex(workspace_pattern),
+                client.get_regex(tab_pattern),
+                client.get_regex(title_pattern),
+                client.get_regex(cwd_pattern),
+                client.get_regex(window_title_pattern),
+            ])
+            .await
+            .try_into()
+            .unwrap();
+            let filter = PaneFilter {
+                workspace: workspace.map_err(to_py_err)?,
+                tab_title: tab_title.map_err(to_py_err)?,
+                title: title.map_err(to_py_err)?,
+                cwd: cwd.map_err(to_py_err)?,
+                window_title: window_title.map_err(to_py_err)?,
+                tty_name,
+                window_id,
+                tab_id,
+                is_active,
+                is_zoomed,
+            };
+            find_panes(&client, &filter).await.map_err(to_py_err)
         })
     }
 
@@ -95,61 +449,61 @@ impl WeztermClient {
     // 5 Prev,
     fn navigate_up<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Up)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn navigate_down<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Down)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn navigate_left<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Left)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn navigate_right<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Right)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn navigate_next<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Next)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn navigate_prev<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             navigate_dir(&client, PaneDirection::Prev)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
@@ -159,134 +513,132 @@ impl WeztermClient {
         direction: String,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             let direction = PaneDirection::direction_from_str(&direction)
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(|msg| PyValueError::new_err(msg.to_string()))?;
 
-            navigate_dir(&client, direction)
-                .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+            navigate_dir(&client, direction).await.map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_up<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Up)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_down<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Down)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_left<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Left)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_right<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Right)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_next<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Next)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn get_pane_in_direction_prev<'a>(
         &self,
         py: Python<'a>,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, PaneDirection::Prev)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
@@ -294,43 +646,39 @@ impl WeztermClient {
         &self,
         py: Python<'a>,
         direction: String,
-        pane_id: Option<usize>,
+        pane_id: Option<PaneId>,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             let direction = PaneDirection::direction_from_str(&direction)
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(|msg| PyValueError::new_err(msg.to_string()))?;
 
             let pane_id = client
                 .connection
-                .resolve_pane_id(pane_id)
+                .resolve_pane_id(pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))?;
+                .map_err(to_py_err)?;
             get_pane_in_direction(&client, pane_id, direction)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     fn current_pane<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            current_pane(&client)
-                .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+            current_pane(&client).await.map_err(to_py_err)
         })
     }
 
     // rpc!(set_focused_pane_id, SetFocusedPane, UnitResponse);
-    fn focus_pane<'a>(&self, py: Python<'a>, pane_id: usize) -> Result<&'a PyAny, PyErr> {
+    fn focus_pane<'a>(&self, py: Python<'a>, pane_id: PaneId) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            focus_pane(&client, pane_id)
-                .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+            focus_pane(&client, pane_id.0).await.map_err(to_py_err)
         })
     }
 
@@ -338,35 +686,31 @@ impl WeztermClient {
     fn write_to_pane<'a>(
         &self,
         py: Python<'a>,
-        pane_id: usize,
+        pane_id: PaneId,
         data: String,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            write_to_pane(&client, pane_id, data.bytes().collect())
+            write_to_pane(&client, pane_id.0, data.bytes().collect())
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
-    fn send_enter<'a>(&self, py: Python<'a>, pane_id: usize) -> Result<&'a PyAny, PyErr> {
+    fn send_enter<'a>(&self, py: Python<'a>, pane_id: PaneId) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            send_enter(&client, pane_id)
-                .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+            send_enter(&client, pane_id.0).await.map_err(to_py_err)
         })
     }
 
-    fn send_esc<'a>(&self, py: Python<'a>, pane_id: usize) -> Result<&'a PyAny, PyErr> {
+    fn send_esc<'a>(&self, py: Python<'a>, pane_id: PaneId) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            send_esc(&client, pane_id)
-                .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+            send_esc(&client, pane_id.0).await.map_err(to_py_err)
         })
     }
 
@@ -374,47 +718,483 @@ impl WeztermClient {
     fn send_paste<'a>(
         &self,
         py: Python<'a>,
-        pane_id: usize,
+        pane_id: PaneId,
         data: String,
     ) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            send_paste(&client, pane_id, data)
+            send_paste(&client, pane_id.0, data)
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
 
     // rpc!(set_focused_pane_id, SetFocusedPane, UnitResponse);
-    fn kill_pane<'a>(&self, py: Python<'a>, pane_id: usize) -> Result<&'a PyAny, PyErr> {
+    fn kill_pane<'a>(&self, py: Python<'a>, pane_id: PaneId) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            kill_pane(&client, pane_id.0).await.map_err(to_py_err)
+        })
+    }
+
+    /// Activate the tab with `tab_id`, or the one at `index` in the window of `pane_id`,
+    /// or the one `relative` tabs away from the tab of `pane_id`.
+    /// A negative `index` counts from the right-most tab.
+    /// Relative moves wrap around unless `wrap` is false, then they stop at either end.
+    /// `pane_id` defaults to the current pane, as with `wezterm cli activate-tab`,
+    /// and like there it cannot be given with `tab_id`
+    #[pyo3(signature = (tab_id=None, index=None, relative=None, wrap=true, pane_id=None))]
+    fn activate_tab<'a>(
+        &self,
+        py: Python<'a>,
+        tab_id: Option<usize>,
+        index: Option<isize>,
+        relative: Option<isize>,
+        wrap: bool,
+        pane_id: Option<PaneId>,
+    ) -> Result<&'a PyAny, PyErr> {
+        let target = match (tab_id, index, relative) {
+            (Some(tab_id), None, None) => TabTarget::Id(tab_id),
+            (None, Some(index), None) => TabTarget::InWindow(TabIndex::Absolute(index)),
+            (None, None, Some(delta)) => TabTarget::InWindow(TabIndex::Relative { delta, wrap }),
+            _ => {
+                return Err(PyValueError::new_err(
+                    "Exactly one of tab_id, index or relative is required",
+                ))
+            }
+        };
+        if tab_id.is_some() && pane_id.is_some() {
+            return Err(PyValueError::new_err(
+                "pane_id only applies to index and relative and cannot be used with tab_id",
+            ));
+        }
+        if !wrap && relative.is_none() {
+            return Err(PyValueError::new_err("wrap only applies to relative"));
+        }
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            activate_tab(&client, target, pane_id.map(|pane| pane.0))
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Run `argv`, or the default program, in a new tab of `window_id`,
+    /// or in a new window of `workspace` when no window is given.
+    /// `cwd` is relative to the current directory of this process and `env` is added
+    /// to the environment of the program. `rows`, `cols` and `workspace` default to
+    /// `initial_rows`, `initial_cols` and `default_workspace` from the wezterm config
+    #[pyo3(signature = (argv=None, cwd=None, env=HashMap::new(), domain=None, window_id=None, workspace=None, rows=None, cols=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn spawn<'a>(
+        &self,
+        py: Python<'a>,
+        argv: Option<Vec<String>>,
+        cwd: Option<PathBuf>,
+        env: HashMap<String, String>,
+        domain: Option<String>,
+        window_id: Option<usize>,
+        workspace: Option<String>,
+        rows: Option<usize>,
+        cols: Option<usize>,
+    ) -> Result<&'a PyAny, PyErr> {
+        if window_id.is_some() && workspace.is_some() {
+            return Err(PyValueError::new_err(
+                "workspace only applies to new windows and cannot be used with window_id",
+            ));
+        }
+        let command = SpawnCommand {
+            argv,
+            cwd,
+            env,
+            domain,
+        };
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            spawn(&client, command, window_id, workspace, rows, cols)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Split `pane_id`, the current pane by default, and run `argv` or the default program
+    /// in the new pane on the `direction` side of it: "left", "right", "top" or "bottom".
+    /// `size` of the new pane is a number of cells or a percentage such as "30%".
+    /// With `top_level` the whole tab is split instead of just the pane.
+    /// The domain defaults to the one of the split pane
+    #[pyo3(signature = (pane_id=None, direction="right", size=SplitSizeArg(SplitSize::Percent(50)), top_level=false, argv=None, cwd=None, env=HashMap::new(), domain=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn split_pane<'a>(
+        &self,
+        py: Python<'a>,
+        pane_id: Option<PaneId>,
+        direction: &str,
+        size: SplitSizeArg,
+        top_level: bool,
+        argv: Option<Vec<String>>,
+        cwd: Option<PathBuf>,
+        env: HashMap<String, String>,
+        domain: Option<String>,
+    ) -> Result<&'a PyAny, PyErr> {
+        let split_request = split::split_request(direction, size, top_level)?;
+        let command = SpawnCommand {
+            argv,
+            cwd,
+            env,
+            domain,
+        };
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            split_pane(&client, pane_id.map(|pane| pane.0), split_request, command)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Move `pane_id`, the current pane by default, into a new tab of `window_id`,
+    /// or of a new window in `workspace`, or else of the window it is already in.
+    /// Returns the moved pane
+    #[pyo3(signature = (pane_id=None, window_id=None, workspace=None))]
+    fn break_pane<'a>(
+        &self,
+        py: Python<'a>,
+        pane_id: Option<PaneId>,
+        window_id: Option<usize>,
+        workspace: Option<String>,
+    ) -> Result<&'a PyAny, PyErr> {
+        if window_id.is_some() && workspace.is_some() {
+            return Err(PyValueError::new_err(
+                "workspace only applies to new windows and cannot be used with window_id",
+            ));
+        }
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            break_pane(&client, pane_id.map(|pane| pane.0), window_id, workspace)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Move `pane_id` next to `target_pane_id` by splitting the target,
+    /// `direction` and `size` are the same as for `split_pane`. Returns the moved pane
+    #[pyo3(signature = (pane_id, target_pane_id, direction="right", size=SplitSizeArg(SplitSize::Percent(50))))]
+    fn join_pane<'a>(
+        &self,
+        py: Python<'a>,
+        pane_id: PaneId,
+        target_pane_id: PaneId,
+        direction: &str,
+        size: SplitSizeArg,
+    ) -> Result<&'a PyAny, PyErr> {
+        let split_request = split::split_request(direction, size, false)?;
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            join_pane(&client, pane_id.0, target_pane_id.0, split_request)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Zoom `pane_id`, the current pane by default, to fill its tab
+    #[pyo3(signature = (pane_id=None))]
+    fn zoom<'a>(&self, py: Python<'a>, pane_id: Option<PaneId>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            set_zoomed(&client, pane_id.map(|pane| pane.0), Some(true))
+                .await
+                .map(|_| ())
+                .map_err(to_py_err)
+        })
+    }
+
+    #[pyo3(signature = (pane_id=None))]
+    fn unzoom<'a>(&self, py: Python<'a>, pane_id: Option<PaneId>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            set_zoomed(&client, pane_id.map(|pane| pane.0), Some(false))
+                .await
+                .map(|_| ())
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Zoom `pane_id` unless it already is zoomed, then unzoom it.
+    /// Returns whether the pane is zoomed now
+    #[pyo3(signature = (pane_id=None))]
+    fn toggle_zoom<'a>(&self, py: Python<'a>, pane_id: Option<PaneId>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            set_zoomed(&client, pane_id.map(|pane| pane.0), None)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Move the split edge of `pane_id`, the current pane by default, by `amount` cells
+    /// in `direction`, one of "Up", "Down", "Left" or "Right".
+    /// The edge moved is the one of the innermost split along that axis.
+    /// The server only resizes active panes, so other panes are focused meanwhile
+    /// and the previous focus is restored after
+    #[pyo3(signature = (pane_id=None, direction="Right", amount=1))]
+    fn adjust_pane_size<'a>(
+        &self,
+        py: Python<'a>,
+        pane_id: Option<PaneId>,
+        direction: &str,
+        amount: usize,
+    ) -> Result<&'a PyAny, PyErr> {
+        let direction = match PaneDirection::direction_from_str(direction) {
+            Ok(PaneDirection::Next | PaneDirection::Prev) | Err(_) => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid resize direction {direction:?}, expected Up, Down, Left or Right"
+                )))
+            }
+            Ok(parsed) => parsed,
+        };
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            adjust_pane_size(&client, pane_id.map(|pane| pane.0), direction, amount)
+                .await
+                .map(Refocused::warn)
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Resize `pane_id`, the current pane by default, to `cols` and/or `rows` cells,
+    /// or to `percent` of its innermost split, by moving the edges of the splits it is in.
+    /// Sizes the layout cannot fit are raised as errors, focus is handled as for
+    /// `adjust_pane_size`. Returns the resized pane
+    #[pyo3(signature = (pane_id=None, cols=None, rows=None, percent=None))]
+    fn resize_pane_to<'a>(
+        &self,
+        py: Python<'a>,
+        pane_id: Option<PaneId>,
+        cols: Option<usize>,
+        rows: Option<usize>,
+        percent: Option<usize>,
+    ) -> Result<&'a PyAny, PyErr> {
+        let target = match (cols, rows, percent) {
+            (None, None, None) => {
+                return Err(PyValueError::new_err(
+                    "One of cols, rows or percent is required",
+                ))
+            }
+            (Some(_), _, Some(_)) | (_, Some(_), Some(_)) => {
+                return Err(PyValueError::new_err(
+                    "percent cannot be used with cols or rows",
+                ))
+            }
+            (Some(0), _, _) | (_, Some(0), _) => {
+                return Err(PyValueError::new_err("cols and rows must be at least 1"))
+            }
+            (_, _, Some(percent)) if !(1..=100).contains(&percent) => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid percent {percent}, expected 1 to 100"
+                )))
+            }
+            (_, _, Some(percent)) => ResizeTarget::Percent(percent),
+            (cols, rows, None) => ResizeTarget::Cells { cols, rows },
+        };
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            resize_pane_to(&client, pane_id.map(|pane| pane.0), target)
+                .await
+                .map(Refocused::warn)
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Set the title of the tab `tab_id`, or of the tab containing `pane_id`,
+    /// which defaults to the current pane
+    #[pyo3(signature = (title, tab_id=None, pane_id=None))]
+    fn set_tab_title<'a>(
+        &self,
+        py: Python<'a>,
+        title: String,
+        tab_id: Option<usize>,
+        pane_id: Option<PaneId>,
+    ) -> Result<&'a PyAny, PyErr> {
+        if tab_id.is_some() && pane_id.is_some() {
+            return Err(PyValueError::new_err(
+                "tab_id and pane_id are mutually exclusive",
+            ));
+        }
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            set_tab_title(&client, title, tab_id, pane_id.map(|pane| pane.0))
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Set the title of the window `window_id`, or of the window containing `pane_id`,
+    /// which defaults to the current pane
+    #[pyo3(signature = (title, window_id=None, pane_id=None))]
+    fn set_window_title<'a>(
+        &self,
+        py: Python<'a>,
+        title: String,
+        window_id: Option<usize>,
+        pane_id: Option<PaneId>,
+    ) -> Result<&'a PyAny, PyErr> {
+        if window_id.is_some() && pane_id.is_some() {
+            return Err(PyValueError::new_err(
+                "window_id and pane_id are mutually exclusive",
+            ));
+        }
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
-            kill_pane(&client, pane_id)
+            set_window_title(&client, title, window_id, pane_id.map(|pane| pane.0))
                 .await
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Names of the workspaces that have windows, sorted
+    fn list_workspaces<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            list_workspaces(&client).await.map_err(to_py_err)
+        })
+    }
+
+    /// Move the window `window_id`, the window of the current pane by default, into the
+    /// workspace `name`. The mux protocol has no way to change the workspace a gui shows,
+    /// so moving windows is as close to switching as a client can get.
+    /// Without a current pane a missing workspace is created with a new window
+    /// running the default program.
+    /// Raises when `name` does not exist yet and `create_if_missing` is false
+    #[pyo3(signature = (name, window_id=None, create_if_missing=true))]
+    fn switch_workspace<'a>(
+        &self,
+        py: Python<'a>,
+        name: String,
+        window_id: Option<usize>,
+        create_if_missing: bool,
+    ) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            switch_workspace(&client, name, window_id, create_if_missing)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    fn rename_workspace<'a>(
+        &self,
+        py: Python<'a>,
+        old: String,
+        new: String,
+    ) -> Result<&'a PyAny, PyErr> {
+        let client = self.clone();
+        self.run(py, async move {
+            let client = client;
+            rename_workspace(&client, old, new).await.map_err(to_py_err)
         })
     }
 
     fn current_workspace<'a>(&self, py: Python<'a>) -> Result<&'a PyAny, PyErr> {
         let client = self.clone();
-        pyo3_asyncio::async_std::future_into_py(py, async move {
+        self.run(py, async move {
             let client = client;
             current_workspace(&client)
                 .await
                 .context("Error while fetching current workspace")
-                .map_err(|msg| PyErr::new::<PyValueError, _>(msg.to_string()))
+                .map_err(to_py_err)
         })
     }
+}
+
+/// Shared implementation of the connection factories
+fn connect_py(
+    py: Python<'_>,
+    config: ConnectionConfig,
+    retries: u32,
+    backoff: f64,
+    timeout: Option<f64>,
+    strict_version: bool,
+    blocking: bool,
+) -> PyResult<&PyAny> {
+    let backoff = Duration::try_from_secs_f64(backoff)
+        .map_err(|err| PyValueError::new_err(format!("Invalid backoff: {err}")))?;
+    let timeout = timeout
+        .map(Duration::try_from_secs_f64)
+        .transpose()
+        .map_err(|err| PyValueError::new_err(format!("Invalid timeout: {err}")))?;
+
+    run_py(py, blocking, async move {
+        let attempts = Connection::establish(config, strict_version, retries, backoff);
+        let result = match timeout {
+            Some(duration) => runtime::timeout(duration, attempts)
+                .await
+                .ok_or(TimedOut(duration))
+                .context("Gave up connecting to wezterm")
+                .and_then(|result| result),
+            None => attempts.await,
+        };
+        result
+            .map(|connection| WeztermClient::with_connection(connection, blocking))
+            .map_err(to_py_err)
+    })
+}
+
+/// Run `future` to completion with the GIL released when `blocking`,
+/// otherwise hand it to asyncio as an awaitable
+fn run_py<F, T>(py: Python<'_>, blocking: bool, future: F) -> PyResult<&PyAny>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject> + Send + 'static,
+{
+    if blocking {
+        let value = py.allow_threads(|| runtime::block_on(future))?;
+        Ok(value.into_py(py).into_ref(py))
+    } else {
+        runtime::future_into_py(py, future)
+    }
+}
+
+async fn server_info(client: &WeztermClient) -> Result<ServerInfo> {
+    Ok(client
+        .connection
+        .get_codec_version(|| codec::GetCodecVersion {})
+        .await
+        .context("Failed to fetch server version")?
+        .into())
+}
 
+async fn list_clients(client: &WeztermClient) -> Result<Vec<ClientInfo>> {
+    Ok(client
+        .connection
+        .list_clients()
+        .await
+        .context("Failed to list clients")?
+        .clients
+        .into_iter()
+        .map(ClientInfo::from)
+        .collect())
 }
 
 async fn current_pane(client: &WeztermClient) -> Result<usize> {
     // Code from wezterm-client::client.rs resolve_pane_id
     let mut clients = client.connection.list_clients().await?.clients;
     clients.retain(|client| client.focused_pane_id.is_some());
-    clients.sort_by(|a, b| b.last_input.cmp(&a.last_input));
+    clients.sort_by_key(|client| std::cmp::Reverse(client.last_input));
     if clients.is_empty() {
         anyhow::bail!(
             "--pane-id was not specified and $WEZTERM_PANE
@@ -442,7 +1222,7 @@ async fn navigate_dir(client: &WeztermClient, direction: PaneDirection) -> Resul
 async fn focus_pane(client: &WeztermClient, pane_id: usize) -> Result<()> {
     client
         .connection
-        .set_focused_pane_id(codec::SetFocusedPane { pane_id })
+        .set_focused_pane_id(|| codec::SetFocusedPane { pane_id })
         .await
         .context("Failed to set pane focus")?;
     Ok(())
@@ -451,7 +1231,10 @@ async fn focus_pane(client: &WeztermClient, pane_id: usize) -> Result<()> {
 async fn write_to_pane(client: &WeztermClient, pane_id: usize, data: Vec<u8>) -> Result<()> {
     client
         .connection
-        .write_to_pane(codec::WriteToPane { pane_id, data })
+        .write_to_pane(|| codec::WriteToPane {
+            pane_id,
+            data: data.clone(),
+        })
         .await
         .context("Unable to write to pane")?;
     Ok(())
@@ -460,7 +1243,7 @@ async fn write_to_pane(client: &WeztermClient, pane_id: usize, data: Vec<u8>) ->
 async fn send_esc(client: &WeztermClient, pane_id: usize) -> Result<()> {
     client
         .connection
-        .key_down(SendKeyDown {
+        .key_down(|| SendKeyDown {
             pane_id,
             event: KeyEvent {
                 key: KeyCode::Char('\u{1b}'),
@@ -476,7 +1259,7 @@ async fn send_esc(client: &WeztermClient, pane_id: usize) -> Result<()> {
 async fn send_enter(client: &WeztermClient, pane_id: usize) -> Result<()> {
     client
         .connection
-        .key_down(SendKeyDown {
+        .key_down(|| SendKeyDown {
             pane_id,
             event: KeyEvent {
                 key: KeyCode::Enter,
@@ -492,7 +1275,10 @@ async fn send_enter(client: &WeztermClient, pane_id: usize) -> Result<()> {
 async fn send_paste(client: &WeztermClient, pane_id: usize, data: String) -> Result<()> {
     client
         .connection
-        .send_paste(codec::SendPaste { pane_id, data })
+        .send_paste(|| codec::SendPaste {
+            pane_id,
+            data: data.clone(),
+        })
         .await
         .context("Failed to paste")?;
     Ok(())
@@ -506,19 +1292,152 @@ async fn get_pane_in_direction(
 ) -> Result<Option<usize>> {
     Ok(client
         .connection
-        .get_pane_direction(codec::GetPaneDirection { pane_id, direction })
+        .get_pane_direction(|| codec::GetPaneDirection { pane_id, direction })
         .await
         .context("Failed to get pane in direction")?
         .pane_id)
 }
 
-async fn find_pane(
-    client: &WeztermClient,
-    workspace_pattern: Option<String>,
-    tab_pattern: Option<String>,
-    title_pattern: Option<String>,
+async fn list_panes(client: &WeztermClient) -> Result<Vec<Pane>> {
+    let responce = client
+        .connection
+        .list_panes()
+        .await
+        .context("Couldn't fetch panes from wezterm")?;
+
+    let mut panes = vec![];
+    for root_node in &responce.tabs {
+        flatten_panes(root_node, &mut panes);
+    }
+    Ok(panes.into_iter().map(Pane::from).collect())
+}
+
+async fn list_windows(client: &WeztermClient) -> Result<Vec<Window>> {
+    let responce = client
+        .connection
+        .list_panes()
+        .await
+        .context("Couldn't fetch panes from wezterm")?;
+    Ok(window::windows_from(&responce))
+}
+
+async fn list_tabs(client: &WeztermClient) -> Result<Vec<Tab>> {
+    Ok(list_windows(client)
+        .await?
+        .into_iter()
+        .flat_map(|window| window.tabs)
+        .collect())
+}
+
+/// Look up `pane_id`, or the current pane when it is None
+async fn resolve_pane(client: &WeztermClient, pane_id: Option<usize>) -> Result<Pane> {
+    let pane_id = client.connection.resolve_pane_id(pane_id).await?;
+    list_panes(client)
+        .await?
+        .into_iter()
+        .find(|pane| pane.pane_id == pane_id)
+        .ok_or_else(|| NoSuchPane(pane_id).into())
+}
+
+async fn pane_at(
+    client: &WeztermClient,
+    tab_id: usize,
+    col: usize,
+    row: usize,
+) -> Result<Option<Pane>> {
+    Ok(list_panes(client)
+        .await?
+        .into_iter()
+        .find(|pane| pane.tab_id == tab_id && Rect::of(pane).contains(col, row)))
+}
+
+async fn neighbors(client: &WeztermClient, pane_id: Option<usize>) -> Result<Neighbors> {
+    let pane_id = client.connection.resolve_pane_id(pane_id).await?;
+    let panes = list_panes(client).await?;
+    let pane = panes
+        .iter()
+        .find(|pane| pane.pane_id == pane_id)
+        .cloned()
+        .ok_or(NoSuchPane(pane_id))?;
+    Ok(geometry::neighbors(&pane, panes))
+}
+
+async fn snapshot(client: &WeztermClient) -> Result<Snapshot> {
+    Ok(client
+        .connection
+        .list_panes()
+        .await
+        .context("Couldn't fetch panes from wezterm")?
+        .into())
+}
+
+async fn list_layouts(client: &WeztermClient) -> Result<Vec<Layout>> {
+    let responce = client
+        .connection
+        .list_panes()
+        .await
+        .context("Couldn't fetch panes from wezterm")?;
+
+    Ok(responce
+        .tabs
+        .iter()
+        .zip(responce.tab_titles)
+        .filter_map(|(root_node, tab_title)| Layout::new(root_node, tab_title))
+        .collect())
+}
+
+/// Criteria for `find_panes`, a pane has to satisfy every one that is set
+#[derive(Default)]
+struct PaneFilter {
+    workspace: Option<Arc<Regex>>,
+    tab_title: Option<Arc<Regex>>,
+    title: Option<Arc<Regex>>,
+    /// Matched against the path of the working directory url
+    cwd: Option<Arc<Regex>>,
+    window_title: Option<Arc<Regex>>,
+    tty_name: Option<String>,
+    window_id: Option<usize>,
+    tab_id: Option<usize>,
+    is_active: Option<bool>,
+    is_zoomed: Option<bool>,
+}
+
+impl PaneFilter {
+    fn matches(&self, pane: &PaneEntry, tab_title: &str, window_title: &str) -> bool {
+        fn is_match(regex: &Option<Arc<Regex>>, text: &str) -> bool {
+            regex.as_ref().is_none_or(|rgx| rgx.is_match(text))
+        }
+        fn is_eq<T: PartialEq>(expected: &Option<T>, actual: &T) -> bool {
+            expected.as_ref().is_none_or(|expected| expected == actual)
+        }
+
+        is_match(&self.workspace, &pane.workspace)
+            && is_match(&self.tab_title, tab_title)
+            && is_match(&self.title, &pane.title)
+            && is_match(&self.window_title, window_title)
+            && self.cwd.as_ref().is_none_or(|rgx| {
+                pane.working_dir
+                    .as_ref()
+                    .is_some_and(|dir| rgx.is_match(dir.url.path()))
+            })
+            && self
+                .tty_name
+                .as_ref()
+                .is_none_or(|tty_name| pane.tty_name.as_ref() == Some(tty_name))
+            && is_eq(&self.window_id, &pane.window_id)
+            && is_eq(&self.tab_id, &pane.tab_id)
+            && is_eq(&self.is_active, &pane.is_active_pane)
+            && is_eq(&self.is_zoomed, &pane.is_zoomed_pane)
+    }
+}
+
+async fn find_pane(
+    client: &WeztermClient,
+    workspace_pattern: Option<String>,
+    tab_pattern: Option<String>,
+    title_pattern: Option<String>,
 ) -> Result<Option<usize>> {
-    let [workspace_regex, tab_regex, title_regex] = futures::future::join_all(vec![
+    let [workspace, tab_title, title] = futures::future::join_all(vec![
         client.get_regex(workspace_pattern),
         client.get_regex(tab_pattern),
         client.get_regex(title_pattern),
@@ -527,44 +1446,45 @@ async fn find_pane(
     .try_into()
     .unwrap();
 
-    let workspace_regex = workspace_regex?;
-    let tab_regex = tab_regex?;
-    let title_regex = title_regex?;
-
-    let workspace_regex: Option<&Regex> = workspace_regex.as_deref();
-    let tab_regex: Option<&Regex> = tab_regex.as_deref();
-    let title_regex: Option<&Regex> = title_regex.as_deref();
+    let filter = PaneFilter {
+        workspace: workspace?,
+        tab_title: tab_title?,
+        title: title?,
+        ..Default::default()
+    };
+    Ok(find_panes(client, &filter)
+        .await?
+        .first()
+        .map(|pane| pane.pane_id))
+}
 
+async fn find_panes(client: &WeztermClient, filter: &PaneFilter) -> Result<Vec<Pane>> {
     let responce = client
         .connection
         .list_panes()
         .await
         .context("Couldn't fetch panes from wezterm")?;
+    Ok(filter_panes(&responce, filter))
+}
 
+fn filter_panes(responce: &codec::ListPanesResponse, filter: &PaneFilter) -> Vec<Pane> {
     let mut panes = Vec::with_capacity(10);
-    for (root_node, tab_title) in responce.tabs.iter().zip(responce.tab_titles) {
-        if tab_regex.map(|rgx| rgx.is_match(&tab_title)) == Some(false) {
-            continue;
-        }
-
+    let mut found = vec![];
+    for (root_node, tab_title) in responce.tabs.iter().zip(&responce.tab_titles) {
         panes.clear();
         flatten_panes(root_node, &mut panes);
 
-        let mut panes = panes.iter().peekable();
-        if workspace_regex.and_then(|rgx| panes.peek().map(|pane| rgx.is_match(&pane.workspace)))
-            == Some(false)
-        {
-            continue;
-        }
-
-        for pane in panes {
-            if title_regex.map(|rgx| rgx.is_match(&pane.title)) == Some(false) {
-                continue;
+        for pane in &panes {
+            let window_title = responce
+                .window_titles
+                .get(&pane.window_id)
+                .map_or("", String::as_str);
+            if filter.matches(pane, tab_title, window_title) {
+                found.push(Pane::from(*pane));
             }
-            return Ok(Some(pane.pane_id));
         }
     }
-    return Ok(None);
+    found
 }
 
 fn flatten_panes<'a>(node: &'a mux::tab::PaneNode, result: &mut Vec<&'a PaneEntry>) {
@@ -585,18 +1505,617 @@ fn flatten_panes<'a>(node: &'a mux::tab::PaneNode, result: &mut Vec<&'a PaneEntr
 async fn kill_pane(client: &WeztermClient, pane_id: usize) -> Result<()> {
     client
         .connection
-        .kill_pane(KillPane { pane_id })
+        .kill_pane(|| KillPane { pane_id })
         .await
         .context("Failed to kill pane")?;
     Ok(())
 }
 
-async fn current_workspace(client: &WeztermClient) -> Result<String> {
-    let (panes_responce, current_pane) = client
+/// Which tab `activate_tab` switches to
+enum TabTarget {
+    Id(usize),
+    /// A tab of the window of the given pane
+    InWindow(TabIndex),
+}
+
+enum TabIndex {
+    Absolute(isize),
+    Relative { delta: isize, wrap: bool },
+}
+
+impl TabIndex {
+    /// Index of the targeted tab in a window of `max` tabs whose tab `active` is active
+    fn tab_index(&self, active: usize, max: usize) -> usize {
+        match *self {
+            // This logic is coupled with TermWindow::activate_tab
+            TabIndex::Absolute(index) if index < 0 => max.saturating_sub(index.unsigned_abs()),
+            TabIndex::Absolute(index) => index as usize,
+            // This logic is coupled with TermWindow::activate_tab_relative
+            TabIndex::Relative { delta, wrap: true } => {
+                (active as isize + delta).rem_euclid(max as isize) as usize
+            }
+            TabIndex::Relative { delta, wrap: false } => {
+                (active as isize + delta).clamp(0, max as isize - 1) as usize
+            }
+        }
+    }
+}
+
+// Port of wezterm/src/cli/activate_tab.rs
+async fn activate_tab(
+    client: &WeztermClient,
+    target: TabTarget,
+    pane_id: Option<usize>,
+) -> Result<()> {
+    let windows = list_windows(client).await?;
+    let tab = match target {
+        TabTarget::Id(tab_id) => windows
+            .iter()
+            .flat_map(|window| &window.tabs)
+            .find(|tab| tab.tab_id == tab_id)
+            .ok_or_else(|| anyhow::anyhow!("No tab with id {tab_id}"))?,
+        TabTarget::InWindow(index) => {
+            let pane_id = client.connection.resolve_pane_id(pane_id).await?;
+            let (tabs, active) = windows
+                .iter()
+                .find_map(|window| {
+                    window
+                        .tabs
+                        .iter()
+                        .position(|tab| tab.panes.iter().any(|pane| pane.pane_id == pane_id))
+                        .map(|active| (&window.tabs, active))
+                })
+                .ok_or(NoSuchPane(pane_id))?;
+            let tab_idx = index.tab_index(active, tabs.len());
+            tabs.get(tab_idx)
+                .ok_or_else(|| anyhow::anyhow!("Tab index {tab_idx} is invalid"))?
+        }
+    };
+
+    let pane = tab.active_pane.as_ref().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Could not determine which pane should be active for tab {}",
+            tab.tab_id
+        )
+    })?;
+    focus_pane(client, pane.pane_id)
+        .await
+        .context("Failed to activate tab")
+}
+
+async fn spawn(
+    client: &WeztermClient,
+    command: SpawnCommand,
+    window_id: Option<usize>,
+    workspace: Option<String>,
+    rows: Option<usize>,
+    cols: Option<usize>,
+) -> Result<Spawned> {
+    let config = connection::configuration();
+    let mut size = config.initial_size(0);
+    size.rows = rows.unwrap_or(size.rows);
+    size.cols = cols.unwrap_or(size.cols);
+    let workspace = workspace
+        .or_else(|| config.default_workspace.clone())
+        .unwrap_or_else(|| mux::DEFAULT_WORKSPACE.to_string());
+    let command_dir = command.command_dir()?;
+
+    Ok(client
         .connection
-        .list_panes()
-        .join(client.connection.resolve_pane_id(None))
-        .await;
+        .spawn_v2(|| codec::SpawnV2 {
+            domain: command.domain(SpawnTabDomain::DefaultDomain),
+            window_id,
+            command: command.command(),
+            command_dir: command_dir.clone(),
+            size,
+            workspace: workspace.clone(),
+        })
+        .await
+        .context("Failed to spawn")?
+        .into())
+}
+
+async fn split_pane(
+    client: &WeztermClient,
+    pane_id: Option<usize>,
+    split_request: SplitRequest,
+    command: SpawnCommand,
+) -> Result<Pane> {
+    let pane_id = client.connection.resolve_pane_id(pane_id).await?;
+    let command_dir = command.command_dir()?;
+    let spawned = client
+        .connection
+        .split_pane(|| codec::SplitPane {
+            pane_id,
+            split_request,
+            command: command.command(),
+            command_dir: command_dir.clone(),
+            domain: command.domain(SpawnTabDomain::CurrentPaneDomain),
+            move_pane_id: None,
+        })
+        .await
+        .context("Failed to split pane")?;
+    resolve_pane(client, Some(spawned.pane_id)).await
+}
+
+async fn break_pane(
+    client: &WeztermClient,
+    pane_id: Option<usize>,
+    window_id: Option<usize>,
+    workspace: Option<String>,
+) -> Result<Pane> {
+    let pane = resolve_pane(client, pane_id).await?;
+    let window_id = match (window_id, &workspace) {
+        (None, None) => Some(pane.window_id),
+        (window_id, _) => window_id,
+    };
+    client
+        .connection
+        .move_pane_to_new_tab(|| codec::MovePaneToNewTab {
+            pane_id: pane.pane_id,
+            window_id,
+            workspace_for_new_window: workspace.clone(),
+        })
+        .await
+        .context("Failed to move pane to a new tab")?;
+    resolve_pane(client, Some(pane.pane_id)).await
+}
+
+async fn join_pane(
+    client: &WeztermClient,
+    pane_id: usize,
+    target_pane_id: usize,
+    split_request: SplitRequest,
+) -> Result<Pane> {
+    client
+        .connection
+        .split_pane(|| codec::SplitPane {
+            pane_id: target_pane_id,
+            split_request,
+            command: None,
+            command_dir: None,
+            domain: SpawnTabDomain::CurrentPaneDomain,
+            move_pane_id: Some(pane_id),
+        })
+        .await
+        .context("Failed to join pane")?;
+    resolve_pane(client, Some(pane_id)).await
+}
+
+/// Zoom or unzoom the pane in its tab, toggle when `zoomed` is None
+async fn set_zoomed(
+    client: &WeztermClient,
+    pane_id: Option<usize>,
+    zoomed: Option<bool>,
+) -> Result<bool> {
+    let pane = resolve_pane(client, pane_id).await?;
+    let zoomed = zoomed.unwrap_or(!(pane.is_active_pane && pane.is_zoomed_pane));
+    client
+        .connection
+        .set_zoomed(|| codec::SetPaneZoomed {
+            containing_tab_id: pane.tab_id,
+            pane_id: pane.pane_id,
+            zoomed,
+        })
+        .await
+        .context("Failed to set pane zoom")?;
+    Ok(zoomed)
+}
+
+/// The server resizes around the active pane of a tab, so make `pane` the active pane
+/// while running `resize`. Focusing a pane also makes its tab the active tab of the window,
+/// so afterwards the previously active pane gets the focus back, and then the tab shown
+/// before, as told by the focus of the clients. Panes of windows with several tabs where
+/// no client has focused a pane are refused, as the shown tab could not be restored.
+/// A failed restore comes back with the result of `resize` rather than failing it
+async fn with_active_pane<T, F: Future<Output = Result<T>>>(
+    client: &WeztermClient,
+    pane: &Pane,
+    resize: impl FnOnce() -> F,
+) -> Result<Refocused<T>> {
+    if pane.is_active_pane {
+        return resize().await.map(Refocused::new);
+    }
+
+    let windows = list_windows(client).await?;
+    let window = windows
+        .iter()
+        .find(|window| window.window_id == pane.window_id)
+        .ok_or(NoSuchPane(pane.pane_id))?;
+    let active_pane_of = |tab_id: usize| {
+        window
+            .tabs
+            .iter()
+            .find(|tab| tab.tab_id == tab_id)
+            .and_then(|tab| tab.active_pane.as_ref())
+            .map(|pane| pane.pane_id)
+    };
+    let mut clients = client.connection.list_clients().await?.clients;
+    clients.sort_by_key(|client| std::cmp::Reverse(client.last_input));
+    let shown_tab_id = clients
+        .iter()
+        .filter_map(|client| client.focused_pane_id)
+        .find_map(|focused| {
+            window
+                .tabs
+                .iter()
+                .find(|tab| tab.panes.iter().any(|pane| pane.pane_id == focused))
+                .map(|tab| tab.tab_id)
+        });
+    if shown_tab_id.is_none() && window.tabs.len() > 1 {
+        anyhow::bail!(
+            "Cannot tell which tab of window {} is shown, focus pane {} before resizing it",
+            window.window_id,
+            pane.pane_id
+        );
+    }
+
+    focus_pane(client, pane.pane_id).await?;
+    let result = resize().await;
+
+    let mut restore = active_pane_of(pane.tab_id).into_iter().collect::<Vec<_>>();
+    if let Some(shown_tab_id) = shown_tab_id.filter(|&tab_id| tab_id != pane.tab_id) {
+        restore.extend(active_pane_of(shown_tab_id));
+    }
+    let mut restored = Ok(());
+    for pane_id in restore {
+        restored = focus_pane(client, pane_id).await;
+        if restored.is_err() {
+            break;
+        }
+    }
+
+    match (result, restored) {
+        (Err(err), Err(restore_err)) => Err(err.context(format!("{restore_err:#}"))),
+        (Ok(value), Err(restore_err)) => Ok(Refocused {
+            value,
+            restore_err: Some(restore_err.context(format!(
+                "Pane {} was resized but the focus could not be restored",
+                pane.pane_id
+            ))),
+        }),
+        (result, Ok(())) => result.map(Refocused::new),
+    }
+}
+
+/// The result of a resize done by `with_active_pane`, which still counts when the
+/// focus could not be restored after it
+struct Refocused<T> {
+    value: T,
+    restore_err: Option<anyhow::Error>,
+}
+
+impl<T> Refocused<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            restore_err: None,
+        }
+    }
+
+    /// Report a failed restore as a RuntimeWarning and return the value
+    fn warn(self) -> T {
+        if let Some(restore_err) = self.restore_err {
+            Python::with_gil(|py| {
+                let message = format!("{restore_err:#}");
+                if let Err(err) = PyErr::warn(py, py.get_type::<PyRuntimeWarning>(), &message, 1) {
+                    err.print(py);
+                }
+            });
+        }
+        self.value
+    }
+}
+
+async fn adjust_pane_size(
+    client: &WeztermClient,
+    pane_id: Option<usize>,
+    direction: PaneDirection,
+    amount: usize,
+) -> Result<Refocused<()>> {
+    let pane = resolve_pane(client, pane_id).await?;
+    with_active_pane(client, &pane, || {
+        send_adjust_pane_size(client, pane.pane_id, direction, amount)
+    })
+    .await
+}
+
+async fn send_adjust_pane_size(
+    client: &WeztermClient,
+    pane_id: usize,
+    direction: PaneDirection,
+    amount: usize,
+) -> Result<()> {
+    client
+        .connection
+        .adjust_pane_size(|| codec::AdjustPaneSize {
+            pane_id,
+            direction,
+            amount,
+        })
+        .await
+        .context("Failed to adjust pane size")?;
+    Ok(())
+}
+
+enum ResizeTarget {
+    Cells {
+        cols: Option<usize>,
+        rows: Option<usize>,
+    },
+    /// Of the innermost split containing the pane
+    Percent(usize),
+}
+
+/// Adjustments are clamped by the server and by the panes nested in the split,
+/// so give up when this many of them do not reach the target
+const MAX_RESIZE_STEPS: usize = 8;
+
+async fn resize_pane_to(
+    client: &WeztermClient,
+    pane_id: Option<usize>,
+    target: ResizeTarget,
+) -> Result<Refocused<Pane>> {
+    let pane = resolve_pane(client, pane_id).await?;
+    let pane_id = pane.pane_id;
+    let refocused = with_active_pane(client, &pane, || async {
+        let (cols, rows) = match target {
+            ResizeTarget::Cells { cols, rows } => (cols, rows),
+            ResizeTarget::Percent(percent) => {
+                percent_of_split(&pane_layout(client, pane_id).await?, pane_id, percent)?
+            }
+        };
+        if let Some(cols) = cols {
+            resize_along(client, pane_id, cols, true).await?;
+        }
+        if let Some(rows) = rows {
+            resize_along(client, pane_id, rows, false).await?;
+        }
+        Ok(())
+    })
+    .await?;
+    Ok(Refocused {
+        value: resolve_pane(client, Some(pane_id)).await?,
+        restore_err: refocused.restore_err,
+    })
+}
+
+/// Columns or rows, as `(cols, rows)`, making `percent` of the innermost split containing
+/// `pane_id` in `layout`, the divider between both sides included
+fn percent_of_split(
+    layout: &Layout,
+    pane_id: usize,
+    percent: usize,
+) -> Result<(Option<usize>, Option<usize>)> {
+    let (split, _) = layout
+        .enclosing_split(pane_id, None)
+        .with_context(|| format!("Pane {pane_id} is not in a split"))?;
+    let (first, second) = split_extents(split, split.direction == "horizontal");
+    let size = ((first + 1 + second) * percent / 100).max(1);
+    Ok(if split.direction == "horizontal" {
+        (Some(size), None)
+    } else {
+        (None, Some(size))
+    })
+}
+
+/// Move the edge of the innermost split containing `pane_id` along one axis
+/// until the pane is `target` cells wide, or high when not `horizontal`
+async fn resize_along(
+    client: &WeztermClient,
+    pane_id: usize,
+    target: usize,
+    horizontal: bool,
+) -> Result<()> {
+    let mut steps = ResizeSteps::new(pane_id, target, horizontal);
+    for _ in 0..MAX_RESIZE_STEPS {
+        let layout = pane_layout(client, pane_id).await?;
+        let Some((direction, amount)) = steps.next(&layout)? else {
+            return Ok(());
+        };
+        send_adjust_pane_size(client, pane_id, direction, amount).await?;
+    }
+    Err(steps.unreachable())
+}
+
+/// The adjustments `resize_along` makes, worked out from the layout after each of them
+struct ResizeSteps {
+    pane_id: usize,
+    target: usize,
+    horizontal: bool,
+    /// Size of the first side of the split before the last adjustment
+    previous_first: Option<usize>,
+}
+
+impl ResizeSteps {
+    fn new(pane_id: usize, target: usize, horizontal: bool) -> Self {
+        Self {
+            pane_id,
+            target,
+            horizontal,
+            previous_first: None,
+        }
+    }
+
+    /// The direction and amount to adjust the pane by next, None once it has the target size.
+    /// Fails when the pane is not in a split along the axis, or when the last adjustment
+    /// did not move the edge at all
+    fn next(&mut self, layout: &Layout) -> Result<Option<(PaneDirection, usize)>> {
+        let pane_id = self.pane_id;
+        let direction = if self.horizontal {
+            "horizontal"
+        } else {
+            "vertical"
+        };
+        let (split, in_first) = layout
+            .enclosing_split(pane_id, Some(direction))
+            .with_context(|| format!("Pane {pane_id} is not in a {direction} split"))?;
+        let (first, second) = split_extents(split, self.horizontal);
+        let wanted_first = if in_first {
+            self.target
+        } else {
+            (first + second).saturating_sub(self.target)
+        };
+        if wanted_first == first {
+            return Ok(None);
+        }
+        if self.previous_first == Some(first) {
+            return Err(self.unreachable());
+        }
+        self.previous_first = Some(first);
+        let direction = match (self.horizontal, wanted_first > first) {
+            (true, true) => PaneDirection::Right,
+            (true, false) => PaneDirection::Left,
+            (false, true) => PaneDirection::Down,
+            (false, false) => PaneDirection::Up,
+        };
+        Ok(Some((direction, wanted_first.abs_diff(first))))
+    }
+
+    fn unreachable(&self) -> anyhow::Error {
+        let unit = if self.horizontal { "columns" } else { "rows" };
+        anyhow::anyhow!(
+            "Pane {} cannot be resized to {} {unit}",
+            self.pane_id,
+            self.target
+        )
+    }
+}
+
+/// Sizes of both sides of `split`, in columns when `horizontal` and rows otherwise
+fn split_extents(split: &Split, horizontal: bool) -> (usize, usize) {
+    if horizontal {
+        (split.first_size.cols, split.second_size.cols)
+    } else {
+        (split.first_size.rows, split.second_size.rows)
+    }
+}
+
+async fn pane_layout(client: &WeztermClient, pane_id: usize) -> Result<Layout> {
+    list_layouts(client)
+        .await?
+        .into_iter()
+        .find(|layout| layout.contains(pane_id))
+        .ok_or_else(|| NoSuchPane(pane_id).into())
+}
+
+async fn set_tab_title(
+    client: &WeztermClient,
+    title: String,
+    tab_id: Option<usize>,
+    pane_id: Option<usize>,
+) -> Result<()> {
+    let tab_id = match tab_id {
+        Some(tab_id) => tab_id,
+        None => resolve_pane(client, pane_id).await?.tab_id,
+    };
+    client
+        .connection
+        .set_tab_title(|| codec::TabTitleChanged {
+            tab_id,
+            title: title.clone(),
+        })
+        .await
+        .context("Failed to set tab title")?;
+    Ok(())
+}
+
+async fn set_window_title(
+    client: &WeztermClient,
+    title: String,
+    window_id: Option<usize>,
+    pane_id: Option<usize>,
+) -> Result<()> {
+    let window_id = match window_id {
+        Some(window_id) => window_id,
+        None => resolve_pane(client, pane_id).await?.window_id,
+    };
+    client
+        .connection
+        .set_window_title(|| codec::WindowTitleChanged {
+            window_id,
+            title: title.clone(),
+        })
+        .await
+        .context("Failed to set window title")?;
+    Ok(())
+}
+
+async fn list_workspaces(client: &WeztermClient) -> Result<Vec<String>> {
+    let mut names: Vec<String> = list_windows(client)
+        .await?
+        .into_iter()
+        .map(|window| window.workspace)
+        .collect();
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+async fn switch_workspace(
+    client: &WeztermClient,
+    name: String,
+    window_id: Option<usize>,
+    create_if_missing: bool,
+) -> Result<()> {
+    let windows = list_windows(client).await?;
+    let exists = windows.iter().any(|window| window.workspace == name);
+    if !create_if_missing && !exists {
+        anyhow::bail!("No workspace named {name:?}");
+    }
+
+    let window_id = match window_id {
+        Some(window_id) => window_id,
+        None => match resolve_pane(client, None).await {
+            Ok(pane) => pane.window_id,
+            Err(err) if exists => return Err(err.context("No window to move to workspace")),
+            Err(_) => {
+                let size = connection::configuration().initial_size(0);
+                client
+                    .connection
+                    .spawn_v2(|| codec::SpawnV2 {
+                        domain: SpawnTabDomain::DefaultDomain,
+                        window_id: None,
+                        command: None,
+                        command_dir: None,
+                        size,
+                        workspace: name.clone(),
+                    })
+                    .await
+                    .context("Failed to create workspace")?;
+                return Ok(());
+            }
+        },
+    };
+    client
+        .connection
+        .set_window_workspace(|| codec::SetWindowWorkspace {
+            window_id,
+            workspace: name.clone(),
+        })
+        .await
+        .context("Failed to move window to workspace")?;
+    Ok(())
+}
+
+async fn rename_workspace(client: &WeztermClient, old: String, new: String) -> Result<()> {
+    client
+        .connection
+        .rename_workspace(|| codec::RenameWorkspace {
+            old_workspace: old.clone(),
+            new_workspace: new.clone(),
+        })
+        .await
+        .context("Failed to rename workspace")?;
+    Ok(())
+}
+
+async fn current_workspace(client: &WeztermClient) -> Result<String> {
+    let (panes_responce, current_pane) = futures::future::join(
+        client.connection.list_panes(),
+        client.connection.resolve_pane_id(None),
+    )
+    .await;
 
     let mut panes = vec![];
     let id = current_pane?;
@@ -612,3 +2131,251 @@ async fn current_workspace(client: &WeztermClient) -> Result<String> {
     }
     anyhow::bail!("No active workspace")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pane::test_entry;
+    use mux::tab::{PaneNode, SerdeUrl, SplitDirection, SplitDirectionAndSize};
+
+    #[test]
+    fn tab_index_counts_negative_indexes_from_the_end() {
+        assert_eq!(TabIndex::Absolute(2).tab_index(0, 4), 2);
+        assert_eq!(TabIndex::Absolute(-1).tab_index(0, 4), 3);
+        assert_eq!(TabIndex::Absolute(-4).tab_index(2, 4), 0);
+        assert_eq!(TabIndex::Absolute(-9).tab_index(2, 4), 0);
+        // Out of range indexes are left for the caller to reject
+        assert_eq!(TabIndex::Absolute(7).tab_index(0, 4), 7);
+    }
+
+    #[test]
+    fn tab_index_relative_wraps_or_clamps() {
+        let relative = |delta, wrap| TabIndex::Relative { delta, wrap };
+        assert_eq!(relative(1, true).tab_index(3, 4), 0);
+        assert_eq!(relative(-1, true).tab_index(0, 4), 3);
+        assert_eq!(relative(-6, true).tab_index(1, 4), 3);
+        assert_eq!(relative(1, false).tab_index(3, 4), 3);
+        assert_eq!(relative(-1, false).tab_index(0, 4), 0);
+        assert_eq!(relative(2, false).tab_index(1, 4), 3);
+    }
+
+    fn regex(pattern: &str) -> Option<Arc<Regex>> {
+        Some(Arc::new(Regex::new(pattern).unwrap()))
+    }
+
+    fn found(responce: &codec::ListPanesResponse, filter: &PaneFilter) -> Vec<usize> {
+        filter_panes(responce, filter)
+            .iter()
+            .map(|pane| pane.pane_id)
+            .collect()
+    }
+
+    /// Size of the area of `node`, dividers included
+    fn node_size(node: &PaneNode) -> term::TerminalSize {
+        match node {
+            PaneNode::Leaf(entry) => entry.size,
+            PaneNode::Split { node, .. } => match node.direction {
+                SplitDirection::Horizontal => term::TerminalSize {
+                    cols: node.first.cols + 1 + node.second.cols,
+                    ..node.first
+                },
+                SplitDirection::Vertical => term::TerminalSize {
+                    rows: node.first.rows + 1 + node.second.rows,
+                    ..node.first
+                },
+            },
+            PaneNode::Empty => Default::default(),
+        }
+    }
+
+    fn split(direction: SplitDirection, left: PaneNode, right: PaneNode) -> PaneNode {
+        PaneNode::Split {
+            node: SplitDirectionAndSize {
+                direction,
+                first: node_size(&left),
+                second: node_size(&right),
+            },
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn side_by_side(left: PaneEntry, right: PaneEntry) -> PaneNode {
+        split(
+            SplitDirection::Horizontal,
+            PaneNode::Leaf(left),
+            PaneNode::Leaf(right),
+        )
+    }
+
+    fn stacked(top: PaneEntry, bottom: PaneEntry) -> PaneNode {
+        split(
+            SplitDirection::Vertical,
+            PaneNode::Leaf(top),
+            PaneNode::Leaf(bottom),
+        )
+    }
+
+    fn layout(tab: PaneNode) -> Layout {
+        Layout::new(&tab, "tab".to_string()).unwrap()
+    }
+
+    #[test]
+    fn pane_filter_checks_the_workspace_of_every_pane() {
+        let mut work = test_entry(0, 1, 41, 0, 39, 24);
+        work.workspace = "work".to_string();
+        let mut other_tab = test_entry(1, 2, 0, 0, 80, 24);
+        other_tab.workspace = "work".to_string();
+        let responce = codec::ListPanesResponse {
+            tabs: vec![
+                side_by_side(test_entry(0, 0, 0, 0, 40, 24), work),
+                PaneNode::Leaf(other_tab),
+            ],
+            tab_titles: vec!["tab 0".to_string(), "tab 1".to_string()],
+            window_titles: [(0, "window".to_string())].into(),
+        };
+        let filter = PaneFilter {
+            workspace: regex("^work$"),
+            ..Default::default()
+        };
+        assert_eq!(found(&responce, &filter), [1, 2]);
+        assert_eq!(found(&responce, &PaneFilter::default()), [0, 1, 2]);
+    }
+
+    #[test]
+    fn pane_filter_looks_up_the_title_of_each_pane_window() {
+        let mut editor = test_entry(1, 1, 0, 0, 80, 24);
+        editor.window_id = 1;
+        let mut untitled = test_entry(2, 2, 0, 0, 80, 24);
+        untitled.window_id = 2;
+        let responce = codec::ListPanesResponse {
+            tabs: vec![
+                PaneNode::Leaf(test_entry(0, 0, 0, 0, 80, 24)),
+                PaneNode::Leaf(editor),
+                PaneNode::Leaf(untitled),
+            ],
+            tab_titles: vec![String::new(); 3],
+            window_titles: [(0, "shell".to_string()), (1, "editor".to_string())].into(),
+        };
+        let filter = |pattern| PaneFilter {
+            window_title: regex(pattern),
+            ..Default::default()
+        };
+        assert_eq!(found(&responce, &filter("^editor$")), [1]);
+        // Windows without a title are matched as ""
+        assert_eq!(found(&responce, &filter("^$")), [2]);
+    }
+
+    #[test]
+    fn pane_filter_cwd_pattern_skips_panes_without_cwd() {
+        let mut in_project = test_entry(0, 0, 0, 0, 40, 24);
+        in_project.working_dir = Some(SerdeUrl {
+            url: "file://host/home/me/project".parse().unwrap(),
+        });
+        let no_cwd = test_entry(0, 1, 41, 0, 39, 24);
+
+        let filter = |pattern| PaneFilter {
+            cwd: regex(pattern),
+            ..Default::default()
+        };
+        assert!(filter("^/home/me/project$").matches(&in_project, "", ""));
+        assert!(!filter("^/tmp").matches(&in_project, "", ""));
+        assert!(!filter("").matches(&no_cwd, "", ""));
+        assert!(PaneFilter::default().matches(&no_cwd, "", ""));
+    }
+
+    #[test]
+    fn pane_filter_compares_tty_names_exactly() {
+        let mut pts = test_entry(0, 0, 0, 0, 80, 24);
+        pts.tty_name = Some("/dev/pts/3".to_string());
+        let no_tty = test_entry(0, 1, 0, 0, 80, 24);
+
+        let filter = |tty_name: &str| PaneFilter {
+            tty_name: Some(tty_name.to_string()),
+            ..Default::default()
+        };
+        assert!(filter("/dev/pts/3").matches(&pts, "", ""));
+        assert!(!filter("/dev/pts/30").matches(&pts, "", ""));
+        assert!(!filter("/dev/pts/3").matches(&no_tty, "", ""));
+    }
+
+    #[test]
+    fn resize_steps_move_the_edge_towards_the_target() {
+        // 40 | 39 columns
+        let tab = layout(side_by_side(
+            test_entry(0, 0, 0, 0, 40, 24),
+            test_entry(0, 1, 41, 0, 39, 24),
+        ));
+        let step = |pane_id, target| ResizeSteps::new(pane_id, target, true).next(&tab).unwrap();
+        assert_eq!(step(0, 30), Some((PaneDirection::Left, 10)));
+        assert_eq!(step(0, 50), Some((PaneDirection::Right, 10)));
+        assert_eq!(step(0, 40), None);
+        // The second side is resized through the first one, (40 + 39) - 30 = 49
+        assert_eq!(step(1, 30), Some((PaneDirection::Right, 9)));
+        assert_eq!(step(1, 50), Some((PaneDirection::Left, 11)));
+        assert_eq!(step(1, 39), None);
+    }
+
+    #[test]
+    fn resize_steps_use_the_innermost_split_along_the_axis() {
+        // 40 columns | 12 rows over 11 rows
+        let tab = layout(split(
+            SplitDirection::Horizontal,
+            PaneNode::Leaf(test_entry(0, 0, 0, 0, 40, 24)),
+            stacked(
+                test_entry(0, 1, 41, 0, 39, 12),
+                test_entry(0, 2, 41, 13, 39, 11),
+            ),
+        ));
+        let mut rows = ResizeSteps::new(2, 5, false);
+        assert_eq!(rows.next(&tab).unwrap(), Some((PaneDirection::Down, 6)));
+        let mut cols = ResizeSteps::new(2, 29, true);
+        assert_eq!(cols.next(&tab).unwrap(), Some((PaneDirection::Right, 10)));
+
+        let err = ResizeSteps::new(0, 5, false).next(&tab).unwrap_err();
+        assert_eq!(err.to_string(), "Pane 0 is not in a vertical split");
+    }
+
+    #[test]
+    fn resize_steps_give_up_when_the_edge_does_not_move() {
+        let tab = layout(side_by_side(
+            test_entry(0, 0, 0, 0, 40, 24),
+            test_entry(0, 1, 41, 0, 39, 24),
+        ));
+        let mut steps = ResizeSteps::new(0, 75, true);
+        assert_eq!(steps.next(&tab).unwrap(), Some((PaneDirection::Right, 35)));
+        // The server clamped the adjustment away, same layout again
+        let err = steps.next(&tab).unwrap_err();
+        assert_eq!(err.to_string(), "Pane 0 cannot be resized to 75 columns");
+
+        let moved = layout(side_by_side(
+            test_entry(0, 0, 0, 0, 70, 24),
+            test_entry(0, 1, 71, 0, 9, 24),
+        ));
+        let mut steps = ResizeSteps::new(0, 75, true);
+        steps.next(&tab).unwrap();
+        assert_eq!(steps.next(&moved).unwrap(), Some((PaneDirection::Right, 5)));
+    }
+
+    #[test]
+    fn percent_of_split_counts_the_divider() {
+        // 40 | 39 columns make 80 with the divider
+        let tab = layout(side_by_side(
+            test_entry(0, 0, 0, 0, 40, 24),
+            test_entry(0, 1, 41, 0, 39, 24),
+        ));
+        assert_eq!(percent_of_split(&tab, 1, 50).unwrap(), (Some(40), None));
+        assert_eq!(percent_of_split(&tab, 0, 25).unwrap(), (Some(20), None));
+        assert_eq!(percent_of_split(&tab, 0, 1).unwrap(), (Some(1), None));
+
+        // 12 over 11 rows make 24
+        let tab = layout(stacked(
+            test_entry(0, 0, 0, 0, 80, 12),
+            test_entry(0, 1, 0, 13, 80, 11),
+        ));
+        assert_eq!(percent_of_split(&tab, 1, 75).unwrap(), (None, Some(18)));
+
+        let tab = layout(PaneNode::Leaf(test_entry(0, 0, 0, 0, 80, 24)));
+        assert!(percent_of_split(&tab, 0, 50).is_err());
+    }
+}
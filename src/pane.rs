@@ -0,0 +1,174 @@
+use crate::geometry::Rect;
+use mux::tab::PaneEntry;
+use pyo3::prelude::*;
+use term::TerminalSize;
+
+/// A pane as reported by the server when the panes were listed
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Pane {
+    pub window_id: usize,
+    pub tab_id: usize,
+    pub pane_id: usize,
+    pub title: String,
+    pub size: PaneSize,
+    /// The url of the current working directory, eg. `file://host/home/user`
+    pub working_dir: Option<String>,
+    pub is_active_pane: bool,
+    pub is_zoomed_pane: bool,
+    pub workspace: String,
+    pub cursor_pos: CursorPosition,
+    /// Stable row index of the top of the visible area
+    pub physical_top: isize,
+    /// Rows from the top of the tab area to the top of this pane
+    pub top_row: usize,
+    /// Columns from the left of the tab area to the left of this pane
+    pub left_col: usize,
+    pub tty_name: Option<String>,
+}
+
+#[pymethods]
+impl Pane {
+    #[getter]
+    fn rect(&self) -> Rect {
+        Rect::of(self)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Pane(pane_id={}, tab_id={}, window_id={}, workspace={:?}, title={:?})",
+            self.pane_id, self.tab_id, self.window_id, self.workspace, self.title
+        )
+    }
+}
+
+impl From<&PaneEntry> for Pane {
+    fn from(entry: &PaneEntry) -> Self {
+        Self {
+            window_id: entry.window_id,
+            tab_id: entry.tab_id,
+            pane_id: entry.pane_id,
+            title: entry.title.clone(),
+            size: entry.size.into(),
+            working_dir: entry
+                .working_dir
+                .as_ref()
+                .map(|dir| dir.url.as_str().to_string()),
+            is_active_pane: entry.is_active_pane,
+            is_zoomed_pane: entry.is_zoomed_pane,
+            workspace: entry.workspace.clone(),
+            cursor_pos: CursorPosition {
+                x: entry.cursor_pos.x,
+                y: entry.cursor_pos.y,
+                shape: format!("{:?}", entry.cursor_pos.shape),
+                visible: entry.cursor_pos.visibility == termwiz::surface::CursorVisibility::Visible,
+            },
+            physical_top: entry.physical_top,
+            top_row: entry.top_row,
+            left_col: entry.left_col,
+            tty_name: entry.tty_name.clone(),
+        }
+    }
+}
+
+/// Dimensions of a pane, the pixel sizes and dpi are zero when unknown
+#[pyclass(get_all)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PaneSize {
+    pub rows: usize,
+    pub cols: usize,
+    pub pixel_width: usize,
+    pub pixel_height: usize,
+    pub dpi: u32,
+}
+
+#[pymethods]
+impl PaneSize {
+    fn __repr__(&self) -> String {
+        format!(
+            "PaneSize(rows={}, cols={}, pixel_width={}, pixel_height={}, dpi={})",
+            self.rows, self.cols, self.pixel_width, self.pixel_height, self.dpi
+        )
+    }
+}
+
+impl From<TerminalSize> for PaneSize {
+    fn from(size: TerminalSize) -> Self {
+        Self {
+            rows: size.rows,
+            cols: size.cols,
+            pixel_width: size.pixel_width,
+            pixel_height: size.pixel_height,
+            dpi: size.dpi,
+        }
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct CursorPosition {
+    pub x: usize,
+    /// Stable row index, subtract `Pane.physical_top` for the visible row
+    pub y: isize,
+    /// Name of the `termwiz::surface::CursorShape`, eg. "BlinkingBar"
+    pub shape: String,
+    pub visible: bool,
+}
+
+#[pymethods]
+impl CursorPosition {
+    fn __repr__(&self) -> String {
+        format!(
+            "CursorPosition(x={}, y={}, shape={:?}, visible={})",
+            self.x,
+            self.y,
+            self.shape,
+            if self.visible { "True" } else { "False" }
+        )
+    }
+}
+
+/// Either a pane id or a `Pane`, which stands in for its id
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PaneId(pub usize);
+
+impl<'a> FromPyObject<'a> for PaneId {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        if let Ok(pane) = ob.extract::<PyRef<Pane>>() {
+            return Ok(Self(pane.pane_id));
+        }
+        ob.extract().map(Self)
+    }
+}
+
+/// A pane of window 0 as the server would list it, the first one of its tab being active
+#[cfg(test)]
+pub(crate) fn test_entry(
+    tab_id: usize,
+    pane_id: usize,
+    left_col: usize,
+    top_row: usize,
+    cols: usize,
+    rows: usize,
+) -> PaneEntry {
+    PaneEntry {
+        window_id: 0,
+        tab_id,
+        pane_id,
+        title: format!("pane {pane_id}"),
+        size: TerminalSize {
+            rows,
+            cols,
+            ..Default::default()
+        },
+        working_dir: None,
+        is_active_pane: left_col == 0 && top_row == 0,
+        is_zoomed_pane: false,
+        workspace: "default".to_string(),
+        cursor_pos: Default::default(),
+        physical_top: 0,
+        top_row,
+        left_col,
+        tty_name: None,
+    }
+}
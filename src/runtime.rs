@@ -0,0 +1,110 @@
+//! The few executor specific operations wezpy needs, backed by whichever
+//! runtime was selected with the `async-std-runtime` or `tokio-runtime` feature.
+//! `tokio-runtime` wins when both are enabled, so it can be turned on without
+//! disabling the default features.
+//! Everything else only relies on runtime agnostic futures and locks.
+
+use pyo3::prelude::*;
+use std::{future::Future, time::Duration};
+
+#[cfg(not(any(feature = "async-std-runtime", feature = "tokio-runtime")))]
+compile_error!("enable one of the `async-std-runtime` or `tokio-runtime` features");
+
+#[cfg(not(feature = "tokio-runtime"))]
+use pyo3_asyncio::async_std as bridge;
+#[cfg(feature = "tokio-runtime")]
+use pyo3_asyncio::tokio as bridge;
+
+/// Wrap `future` in an asyncio awaitable
+pub(crate) fn future_into_py<F, T>(py: Python<'_>, future: F) -> PyResult<&PyAny>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: IntoPy<PyObject>,
+{
+    bridge::future_into_py(py, future)
+}
+
+/// The asyncio event loop driving the current task, if any
+pub(crate) fn current_event_loop(py: Python<'_>) -> Option<&PyAny> {
+    bridge::get_current_locals(py)
+        .ok()
+        .map(|locals| locals.event_loop(py))
+}
+
+/// Drive `future` to completion on the current thread
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    #[cfg(not(feature = "tokio-runtime"))]
+    return async_std::task::block_on(future);
+    #[cfg(feature = "tokio-runtime")]
+    return bridge::get_runtime().block_on(future);
+}
+
+/// Run a blocking closure on the runtime's blocking thread pool
+pub(crate) async fn spawn_blocking<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    #[cfg(not(feature = "tokio-runtime"))]
+    return async_std::task::spawn_blocking(f).await;
+    #[cfg(feature = "tokio-runtime")]
+    return bridge::get_runtime()
+        .spawn_blocking(f)
+        .await
+        .expect("blocking task panicked");
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(not(feature = "tokio-runtime"))]
+    async_std::task::sleep(duration).await;
+    #[cfg(feature = "tokio-runtime")]
+    tokio::time::sleep(duration).await;
+}
+
+/// Resolve to `None` if `future` did not complete within `duration`
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    #[cfg(not(feature = "tokio-runtime"))]
+    return async_std::future::timeout(duration, future).await.ok();
+    #[cfg(feature = "tokio-runtime")]
+    return tokio::time::timeout(duration, future).await.ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn timeout_returns_the_output_of_futures_that_finish_in_time() {
+        let output = block_on(timeout(Duration::from_secs(5), async { 42 }));
+        assert_eq!(output, Some(42));
+    }
+
+    #[test]
+    fn timeout_gives_up_on_slow_futures() {
+        let started = Instant::now();
+        let output = block_on(timeout(Duration::from_millis(10), async {
+            sleep(Duration::from_secs(5)).await;
+            42
+        }));
+        assert_eq!(output, None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn sleep_waits_at_least_the_duration() {
+        let started = Instant::now();
+        block_on(sleep(Duration::from_millis(20)));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn spawn_blocking_returns_the_closure_result() {
+        let thread = std::thread::current().id();
+        let (output, other_thread) = block_on(spawn_blocking(move || {
+            (42, std::thread::current().id() != thread)
+        }));
+        assert_eq!(output, 42);
+        assert!(other_thread);
+    }
+}
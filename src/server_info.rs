@@ -0,0 +1,43 @@
+use codec::{GetCodecVersionResponse, CODEC_VERSION};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+/// Version details reported by the wezterm server we are connected to
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct ServerInfo {
+    codec_vers: usize,
+    version_string: String,
+    executable_path: PathBuf,
+    config_file_path: Option<PathBuf>,
+    /// Codec version wezpy was built against
+    client_codec_vers: usize,
+}
+
+#[pymethods]
+impl ServerInfo {
+    /// Whether the server speaks the same codec version as wezpy
+    #[getter]
+    fn compatible(&self) -> bool {
+        self.codec_vers == self.client_codec_vers
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ServerInfo(version_string={:?}, codec_vers={}, client_codec_vers={})",
+            self.version_string, self.codec_vers, self.client_codec_vers
+        )
+    }
+}
+
+impl From<GetCodecVersionResponse> for ServerInfo {
+    fn from(response: GetCodecVersionResponse) -> Self {
+        Self {
+            codec_vers: response.codec_vers,
+            version_string: response.version_string,
+            executable_path: response.executable_path,
+            config_file_path: response.config_file_path,
+            client_codec_vers: CODEC_VERSION,
+        }
+    }
+}
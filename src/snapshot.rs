@@ -0,0 +1,314 @@
+use crate::{
+    layout::Layout,
+    pane::Pane,
+    window::{self, Tab, Window},
+};
+use codec::ListPanesResponse;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// Everything the server reported in a single listing of the panes
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Snapshot {
+    windows: Vec<Window>,
+    panes: Vec<Pane>,
+    layouts: Vec<Layout>,
+}
+
+#[pymethods]
+impl Snapshot {
+    #[getter]
+    fn tabs(&self) -> Vec<Tab> {
+        self.tabs_iter().cloned().collect()
+    }
+
+    /// Names of the workspaces that have windows, sorted
+    #[getter]
+    fn workspaces(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .windows
+            .iter()
+            .map(|window| window.workspace.clone())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Snapshot(windows={}, tabs={}, panes={})",
+            self.windows.len(),
+            self.tabs_iter().count(),
+            self.panes.len()
+        )
+    }
+}
+
+impl Snapshot {
+    fn tabs_iter(&self) -> impl Iterator<Item = &Tab> {
+        self.windows.iter().flat_map(|window| &window.tabs)
+    }
+}
+
+impl From<ListPanesResponse> for Snapshot {
+    fn from(response: ListPanesResponse) -> Self {
+        let windows = window::windows_from(&response);
+        let panes = windows
+            .iter()
+            .flat_map(|window| &window.tabs)
+            .flat_map(|tab| tab.panes.iter().cloned())
+            .collect();
+        let layouts = response
+            .tabs
+            .iter()
+            .zip(response.tab_titles)
+            .filter_map(|(root_node, tab_title)| Layout::new(root_node, tab_title))
+            .collect();
+        Self {
+            windows,
+            panes,
+            layouts,
+        }
+    }
+}
+
+/// What changed between two snapshots.
+/// Changes to existing objects are `(old, new)` pairs
+#[pyclass(frozen, get_all)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SnapshotDiff {
+    windows_added: Vec<Window>,
+    windows_removed: Vec<Window>,
+    windows_retitled: Vec<(Window, Window)>,
+    /// Windows assigned to another workspace
+    windows_moved: Vec<(Window, Window)>,
+    tabs_added: Vec<Tab>,
+    tabs_removed: Vec<Tab>,
+    tabs_retitled: Vec<(Tab, Tab)>,
+    /// Tabs moved to another window
+    tabs_moved: Vec<(Tab, Tab)>,
+    /// Tabs whose active pane changed
+    tabs_refocused: Vec<(Tab, Tab)>,
+    panes_added: Vec<Pane>,
+    panes_removed: Vec<Pane>,
+    panes_retitled: Vec<(Pane, Pane)>,
+    /// Panes moved to another tab or window
+    panes_moved: Vec<(Pane, Pane)>,
+    panes_resized: Vec<(Pane, Pane)>,
+    /// Panes that became or stopped being the active pane of their tab
+    panes_refocused: Vec<(Pane, Pane)>,
+}
+
+#[pymethods]
+impl SnapshotDiff {
+    /// False when nothing changed
+    fn __bool__(&self) -> bool {
+        !(self.windows_added.is_empty()
+            && self.windows_removed.is_empty()
+            && self.windows_retitled.is_empty()
+            && self.windows_moved.is_empty()
+            && self.tabs_added.is_empty()
+            && self.tabs_removed.is_empty()
+            && self.tabs_retitled.is_empty()
+            && self.tabs_moved.is_empty()
+            && self.tabs_refocused.is_empty()
+            && self.panes_added.is_empty()
+            && self.panes_removed.is_empty()
+            && self.panes_retitled.is_empty()
+            && self.panes_moved.is_empty()
+            && self.panes_resized.is_empty()
+            && self.panes_refocused.is_empty())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SnapshotDiff(windows=+{}/-{}, tabs=+{}/-{}, panes=+{}/-{})",
+            self.windows_added.len(),
+            self.windows_removed.len(),
+            self.tabs_added.len(),
+            self.tabs_removed.len(),
+            self.panes_added.len(),
+            self.panes_removed.len()
+        )
+    }
+}
+
+/// Compare two snapshots, `old` is expected to have been taken first
+#[pyfunction]
+pub(crate) fn diff(old: &Snapshot, new: &Snapshot) -> SnapshotDiff {
+    let mut diff = SnapshotDiff::default();
+
+    let windows = Matched::new(&old.windows, &new.windows, |window| window.window_id);
+    diff.windows_added = windows.added;
+    diff.windows_removed = windows.removed;
+    for (old, new) in windows.kept {
+        if old.title != new.title {
+            diff.windows_retitled.push((old.clone(), new.clone()));
+        }
+        if old.workspace != new.workspace {
+            diff.windows_moved.push((old, new));
+        }
+    }
+
+    let old_tabs: Vec<Tab> = old.tabs_iter().cloned().collect();
+    let new_tabs: Vec<Tab> = new.tabs_iter().cloned().collect();
+    let tabs = Matched::new(&old_tabs, &new_tabs, |tab| tab.tab_id);
+    diff.tabs_added = tabs.added;
+    diff.tabs_removed = tabs.removed;
+    for (old, new) in tabs.kept {
+        if old.title != new.title {
+            diff.tabs_retitled.push((old.clone(), new.clone()));
+        }
+        if old.window_id != new.window_id {
+            diff.tabs_moved.push((old.clone(), new.clone()));
+        }
+        let active_pane_id = |tab: &Tab| tab.active_pane.as_ref().map(|pane| pane.pane_id);
+        if active_pane_id(&old) != active_pane_id(&new) {
+            diff.tabs_refocused.push((old, new));
+        }
+    }
+
+    let panes = Matched::new(&old.panes, &new.panes, |pane| pane.pane_id);
+    diff.panes_added = panes.added;
+    diff.panes_removed = panes.removed;
+    for (old, new) in panes.kept {
+        if old.title != new.title {
+            diff.panes_retitled.push((old.clone(), new.clone()));
+        }
+        if old.tab_id != new.tab_id || old.window_id != new.window_id {
+            diff.panes_moved.push((old.clone(), new.clone()));
+        }
+        if old.size != new.size {
+            diff.panes_resized.push((old.clone(), new.clone()));
+        }
+        if old.is_active_pane != new.is_active_pane {
+            diff.panes_refocused.push((old, new));
+        }
+    }
+
+    diff
+}
+
+/// Objects of two listings paired up by id
+struct Matched<T> {
+    added: Vec<T>,
+    removed: Vec<T>,
+    kept: Vec<(T, T)>,
+}
+
+impl<T: Clone> Matched<T> {
+    fn new(old: &[T], new: &[T], id: impl Fn(&T) -> usize) -> Self {
+        let mut old_by_id: HashMap<usize, &T> = old.iter().map(|item| (id(item), item)).collect();
+        let mut matched = Self {
+            added: vec![],
+            removed: vec![],
+            kept: vec![],
+        };
+        for item in new {
+            match old_by_id.remove(&id(item)) {
+                Some(old_item) => matched.kept.push((old_item.clone(), item.clone())),
+                None => matched.added.push(item.clone()),
+            }
+        }
+        matched.removed = old
+            .iter()
+            .filter(|item| old_by_id.contains_key(&id(item)))
+            .cloned()
+            .collect();
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pane::test_entry;
+    use mux::tab::{PaneEntry, PaneNode, SplitDirection, SplitDirectionAndSize};
+
+    fn snapshot(tabs: Vec<PaneNode>) -> Snapshot {
+        ListPanesResponse {
+            tab_titles: (0..tabs.len())
+                .map(|index| format!("tab {index}"))
+                .collect(),
+            tabs,
+            window_titles: [(0, "window".to_string())].into(),
+        }
+        .into()
+    }
+
+    fn side_by_side(left: PaneEntry, right: PaneEntry) -> PaneNode {
+        PaneNode::Split {
+            node: SplitDirectionAndSize {
+                direction: SplitDirection::Horizontal,
+                first: left.size,
+                second: right.size,
+            },
+            left: Box::new(PaneNode::Leaf(left)),
+            right: Box::new(PaneNode::Leaf(right)),
+        }
+    }
+
+    fn pane_ids(panes: &[Pane]) -> Vec<usize> {
+        panes.iter().map(|pane| pane.pane_id).collect()
+    }
+
+    #[test]
+    fn snapshot_groups_panes_by_tab_and_window() {
+        let old = snapshot(vec![
+            side_by_side(
+                test_entry(0, 0, 0, 0, 40, 24),
+                test_entry(0, 1, 41, 0, 39, 24),
+            ),
+            PaneNode::Leaf(test_entry(1, 2, 0, 0, 80, 24)),
+        ]);
+        assert_eq!(old.windows.len(), 1);
+        assert_eq!(old.tabs().len(), 2);
+        assert_eq!(pane_ids(&old.panes), [0, 1, 2]);
+        assert_eq!(old.layouts.len(), 2);
+        assert_eq!(old.workspaces(), ["default"]);
+    }
+
+    #[test]
+    fn identical_snapshots_do_not_differ() {
+        let tabs = || vec![PaneNode::Leaf(test_entry(0, 0, 0, 0, 80, 24))];
+        let diff = diff(&snapshot(tabs()), &snapshot(tabs()));
+        assert!(!diff.__bool__());
+    }
+
+    #[test]
+    fn diff_reports_changes_by_id() {
+        let old = snapshot(vec![
+            side_by_side(
+                test_entry(0, 0, 0, 0, 40, 24),
+                test_entry(0, 1, 41, 0, 39, 24),
+            ),
+            PaneNode::Leaf(test_entry(1, 2, 0, 0, 80, 24)),
+        ]);
+        let mut resized = test_entry(0, 1, 31, 0, 49, 24);
+        resized.title = "vim".to_string();
+        let new = snapshot(vec![
+            side_by_side(test_entry(0, 0, 0, 0, 30, 24), resized),
+            PaneNode::Leaf(test_entry(2, 3, 0, 0, 80, 24)),
+        ]);
+
+        let diff = diff(&old, &new);
+        assert!(diff.__bool__());
+        assert!(diff.windows_added.is_empty() && diff.windows_removed.is_empty());
+        assert_eq!(diff.tabs_added.len(), 1);
+        assert_eq!(diff.tabs_added[0].tab_id, 2);
+        assert_eq!(diff.tabs_removed[0].tab_id, 1);
+        assert_eq!(pane_ids(&diff.panes_added), [3]);
+        assert_eq!(pane_ids(&diff.panes_removed), [2]);
+        assert_eq!(diff.panes_retitled.len(), 1);
+        assert_eq!(diff.panes_retitled[0].1.title, "vim");
+        let resized: Vec<_> = diff
+            .panes_resized
+            .iter()
+            .map(|(old, new)| (old.pane_id, old.size.cols, new.size.cols))
+            .collect();
+        assert_eq!(resized, [(0, 40, 30), (1, 39, 49)]);
+        assert!(diff.panes_moved.is_empty() && diff.panes_refocused.is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use crate::pane::PaneSize;
+use anyhow::{Context, Result};
+use codec::SpawnResponse;
+use config::keyassignment::SpawnTabDomain;
+use pty::CommandBuilder;
+use pyo3::prelude::*;
+use std::{collections::HashMap, path::PathBuf};
+
+/// Ids of what `spawn` created
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Spawned {
+    pane_id: usize,
+    tab_id: usize,
+    window_id: usize,
+    size: PaneSize,
+}
+
+#[pymethods]
+impl Spawned {
+    fn __repr__(&self) -> String {
+        format!(
+            "Spawned(pane_id={}, tab_id={}, window_id={})",
+            self.pane_id, self.tab_id, self.window_id
+        )
+    }
+}
+
+impl From<SpawnResponse> for Spawned {
+    fn from(response: SpawnResponse) -> Self {
+        Self {
+            pane_id: response.pane_id,
+            tab_id: response.tab_id,
+            window_id: response.window_id,
+            size: response.size.into(),
+        }
+    }
+}
+
+/// What to run in a new pane, shared by `spawn` and `split_pane`
+pub(crate) struct SpawnCommand {
+    pub argv: Option<Vec<String>>,
+    pub cwd: Option<PathBuf>,
+    pub env: HashMap<String, String>,
+    pub domain: Option<String>,
+}
+
+impl SpawnCommand {
+    /// None lets the domain run its default program
+    pub(crate) fn command(&self) -> Option<CommandBuilder> {
+        let mut builder = match &self.argv {
+            Some(argv) => CommandBuilder::from_argv(argv.iter().map(Into::into).collect()),
+            None if self.env.is_empty() => return None,
+            None => CommandBuilder::new_default_prog(),
+        };
+        for (key, value) in &self.env {
+            builder.env(key, value);
+        }
+        Some(builder)
+    }
+
+    /// `cwd` resolved against our own working directory, like `wezterm cli spawn` does
+    pub(crate) fn command_dir(&self) -> Result<Option<String>> {
+        let Some(cwd) = &self.cwd else {
+            return Ok(None);
+        };
+        let cwd = std::env::current_dir()?.join(cwd);
+        let cwd = cwd
+            .to_str()
+            .with_context(|| format!("{} is not representable as a string", cwd.display()))?;
+        Ok(Some(cwd.to_string()))
+    }
+
+    /// The named domain, `fallback` when none was given
+    pub(crate) fn domain(&self, fallback: SpawnTabDomain) -> SpawnTabDomain {
+        self.domain
+            .clone()
+            .map_or(fallback, SpawnTabDomain::DomainName)
+    }
+}
@@ -0,0 +1,108 @@
+use mux::tab::{SplitDirection, SplitRequest, SplitSize};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBool};
+
+/// A split size given either as a number of cells or as a percentage string, eg. "30%"
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SplitSizeArg(pub SplitSize);
+
+impl<'a> FromPyObject<'a> for SplitSizeArg {
+    fn extract(ob: &'a PyAny) -> PyResult<Self> {
+        // bool is an int subclass, but True is not a size
+        let size = if ob.is_instance_of::<PyBool>() {
+            None
+        } else if let Ok(cells) = ob.extract::<usize>() {
+            Self::cells(cells)
+        } else if let Ok(size) = ob.extract::<&str>() {
+            Self::parse(size)
+        } else {
+            None
+        };
+        match size {
+            Some(size) => Ok(size),
+            None => Err(PyValueError::new_err(format!(
+                "Invalid split size {}, expected at least 1 cell or 1% to 100%",
+                ob.repr()?
+            ))),
+        }
+    }
+}
+
+impl SplitSizeArg {
+    fn cells(cells: usize) -> Option<Self> {
+        (cells > 0).then_some(Self(SplitSize::Cells(cells)))
+    }
+
+    /// A number of cells or a "N%" percentage
+    fn parse(size: &str) -> Option<Self> {
+        match size.trim().strip_suffix('%') {
+            Some(percent) => match percent.trim().parse() {
+                Ok(percent @ 1..=100) => Some(Self(SplitSize::Percent(percent))),
+                _ => None,
+            },
+            None => Self::cells(size.trim().parse().ok()?),
+        }
+    }
+}
+
+/// Build the request for putting a new pane on the `side` of the split pane,
+/// one of "left", "right", "top" or "bottom"
+pub(crate) fn split_request(
+    side: &str,
+    size: SplitSizeArg,
+    top_level: bool,
+) -> PyResult<SplitRequest> {
+    let (direction, target_is_second) = match side.to_lowercase().as_str() {
+        "left" => (SplitDirection::Horizontal, false),
+        "right" => (SplitDirection::Horizontal, true),
+        "top" | "up" => (SplitDirection::Vertical, false),
+        "bottom" | "down" => (SplitDirection::Vertical, true),
+        _ => {
+            return Err(PyValueError::new_err(format!(
+                "Invalid split direction {side:?}, expected left, right, top or bottom"
+            )))
+        }
+    };
+    Ok(SplitRequest {
+        direction,
+        target_is_second,
+        top_level,
+        size: size.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(size: &str) -> Option<SplitSize> {
+        SplitSizeArg::parse(size).map(|size| size.0)
+    }
+
+    #[test]
+    fn parses_cells_and_percentages() {
+        assert_eq!(parse("12"), Some(SplitSize::Cells(12)));
+        assert_eq!(parse(" 30% "), Some(SplitSize::Percent(30)));
+        assert_eq!(parse("1%"), Some(SplitSize::Percent(1)));
+        assert_eq!(parse("100 %"), Some(SplitSize::Percent(100)));
+    }
+
+    #[test]
+    fn rejects_empty_sizes() {
+        for size in ["0", "0%", "101%", "-3", "-3%", "%", "", "half"] {
+            assert_eq!(parse(size), None, "{size:?}");
+        }
+        assert!(SplitSizeArg::cells(0).is_none());
+    }
+
+    #[test]
+    fn split_request_sides() {
+        let size = SplitSizeArg(SplitSize::Cells(5));
+        let request = split_request("Left", size, true).unwrap();
+        assert_eq!(request.direction, SplitDirection::Horizontal);
+        assert!(!request.target_is_second);
+        assert!(request.top_level);
+        let request = split_request("down", size, false).unwrap();
+        assert_eq!(request.direction, SplitDirection::Vertical);
+        assert!(request.target_is_second);
+    }
+}
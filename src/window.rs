@@ -0,0 +1,98 @@
+use crate::pane::Pane;
+use codec::ListPanesResponse;
+use pyo3::prelude::*;
+
+/// A gui window and its tabs, in tab bar order
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Window {
+    pub window_id: usize,
+    pub title: String,
+    pub workspace: String,
+    pub tabs: Vec<Tab>,
+}
+
+#[pymethods]
+impl Window {
+    fn __repr__(&self) -> String {
+        format!(
+            "Window(window_id={}, title={:?}, workspace={:?}, tabs={})",
+            self.window_id,
+            self.title,
+            self.workspace,
+            self.tabs.len()
+        )
+    }
+}
+
+#[pyclass(get_all)]
+#[derive(Clone, Debug)]
+pub(crate) struct Tab {
+    pub tab_id: usize,
+    pub window_id: usize,
+    pub title: String,
+    /// Position in the tab bar of its window, starting at 0
+    index: usize,
+    pub active_pane: Option<Pane>,
+    zoomed_pane: Option<Pane>,
+    pub panes: Vec<Pane>,
+}
+
+#[pymethods]
+impl Tab {
+    fn __repr__(&self) -> String {
+        format!(
+            "Tab(tab_id={}, window_id={}, index={}, title={:?}, panes={})",
+            self.tab_id,
+            self.window_id,
+            self.index,
+            self.title,
+            self.panes.len()
+        )
+    }
+}
+
+/// Group the listed tabs by window, keeping the order the server listed them in
+pub(crate) fn windows_from(response: &ListPanesResponse) -> Vec<Window> {
+    let mut windows: Vec<Window> = vec![];
+    let mut entries = vec![];
+    for (root_node, tab_title) in response.tabs.iter().zip(&response.tab_titles) {
+        entries.clear();
+        crate::flatten_panes(root_node, &mut entries);
+        let Some(first) = entries.first() else {
+            continue;
+        };
+
+        let window = match windows
+            .iter_mut()
+            .position(|window| window.window_id == first.window_id)
+        {
+            Some(index) => &mut windows[index],
+            None => {
+                windows.push(Window {
+                    window_id: first.window_id,
+                    title: response
+                        .window_titles
+                        .get(&first.window_id)
+                        .cloned()
+                        .unwrap_or_default(),
+                    workspace: first.workspace.clone(),
+                    tabs: vec![],
+                });
+                windows.last_mut().unwrap()
+            }
+        };
+
+        let panes: Vec<Pane> = entries.iter().map(|entry| Pane::from(*entry)).collect();
+        window.tabs.push(Tab {
+            tab_id: first.tab_id,
+            window_id: first.window_id,
+            title: tab_title.clone(),
+            index: window.tabs.len(),
+            active_pane: panes.iter().find(|pane| pane.is_active_pane).cloned(),
+            zoomed_pane: panes.iter().find(|pane| pane.is_zoomed_pane).cloned(),
+            panes,
+        });
+    }
+    windows
+}
@@ -1244,8 +1244,10 @@ impl Client {
         Ok(Self::new(local_domain_id, reconnectable))
     }
 
+    /// Without a `local_domain_id` the connection is not tied to a mux domain,
+    /// and the client thread ends instead of reconnecting when it drops
     pub fn new_tls(
-        local_domain_id: DomainId,
+        local_domain_id: Option<DomainId>,
         tls_client: &TlsDomainClient,
         ui: &mut ConnectionUI,
     ) -> anyhow::Result<Self> {
@@ -1253,18 +1255,19 @@ impl Client {
             Reconnectable::new(ClientDomainConfig::Tls(tls_client.clone()), None);
         let no_auto_start = true;
         reconnectable.connect(true, ui, no_auto_start)?;
-        Ok(Self::new(Some(local_domain_id), reconnectable))
+        Ok(Self::new(local_domain_id, reconnectable))
     }
 
+    /// See `new_tls` for `local_domain_id`
     pub fn new_ssh(
-        local_domain_id: DomainId,
+        local_domain_id: Option<DomainId>,
         ssh_dom: &SshDomain,
         ui: &mut ConnectionUI,
     ) -> anyhow::Result<Self> {
         let mut reconnectable = Reconnectable::new(ClientDomainConfig::Ssh(ssh_dom.clone()), None);
         let no_auto_start = true;
         reconnectable.connect(true, ui, no_auto_start)?;
-        Ok(Self::new(Some(local_domain_id), reconnectable))
+        Ok(Self::new(local_domain_id, reconnectable))
     }
 
     pub async fn send_pdu(&self, pdu: Pdu) -> anyhow::Result<Pdu> {
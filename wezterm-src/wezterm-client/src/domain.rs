@@ -967,8 +967,12 @@ impl Domain for ClientDomain {
                             no_auto_start,
                         )
                     }
-                    ClientDomainConfig::Tls(tls) => Client::new_tls(domain_id, tls, &mut cloned_ui),
-                    ClientDomainConfig::Ssh(ssh) => Client::new_ssh(domain_id, ssh, &mut cloned_ui),
+                    ClientDomainConfig::Tls(tls) => {
+                        Client::new_tls(Some(domain_id), tls, &mut cloned_ui)
+                    }
+                    ClientDomainConfig::Ssh(ssh) => {
+                        Client::new_ssh(Some(domain_id), ssh, &mut cloned_ui)
+                    }
                 })
                 .await?;
 